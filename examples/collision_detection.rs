@@ -1,4 +1,7 @@
-use crate2d::{collision::ColliderShape, glam::Vec2, Box2D, Circle, ForceGenerator, RigidBody};
+use crate2d::{
+    collision::ColliderShape, glam::Vec2, triangulate, Box2D, Capsule, Circle, ConvexPolygon,
+    ForceGenerator, Heightfield, Polyline, RigidBody, TriMesh,
+};
 
 use macroquad::prelude::*;
 
@@ -13,11 +16,37 @@ struct Demo {
     circle2: ColliderShape,
     box1: ColliderShape,
     box2: ColliderShape,
+    hexagon: ColliderShape,
+    capsule: ColliderShape,
+    hills: ColliderShape,
+    bumps: ColliderShape,
+    arch: ColliderShape,
     colliding: Vec<ColliderShape>,
 }
 
 impl Demo {
     pub fn new() -> Self {
+        let mut hexagon = ConvexPolygon::regular(6, 30.0).unwrap();
+        hexagon.position = Vec2::new(300.0, 275.0);
+        let mut capsule = Capsule::vertical(25.0, 15.0);
+        capsule.position = Vec2::new(300.0, 400.0);
+        capsule.rotation = f32::to_radians(60.0);
+        // Heights go up the screen, against the y axis
+        let heights: Vec<f32> = (0..12).map(|i| (i as f32 * 0.8).sin() + 1.0).collect();
+        let mut bumps = Heightfield::new(heights, Vec2::new(20.0, -30.0)).unwrap();
+        bumps.position = Vec2::new(550.0, 500.0);
+        let arch_outline = [
+            Vec2::new(550.0, 300.0),
+            Vec2::new(570.0, 300.0),
+            Vec2::new(590.0, 260.0),
+            Vec2::new(630.0, 260.0),
+            Vec2::new(650.0, 300.0),
+            Vec2::new(670.0, 300.0),
+            Vec2::new(670.0, 230.0),
+            Vec2::new(550.0, 230.0),
+        ];
+        let arch_triangles = triangulate(&arch_outline).unwrap();
+        let arch = TriMesh::new(&arch_outline, &arch_triangles).unwrap();
         Self {
             circle1: ColliderShape::Circle(Circle::new(Vec2::new(200.0, 200.0), 7.5)),
             circle2: ColliderShape::Circle(Circle::new(Vec2::new(400.0, 200.0), 10.0)),
@@ -31,6 +60,20 @@ impl Demo {
                 Vec2::new(425.0, 375.0),
                 f32::to_radians(45.0),
             )),
+            hexagon: ColliderShape::Polygon(hexagon),
+            capsule: ColliderShape::Capsule(capsule),
+            hills: ColliderShape::Polyline(
+                Polyline::new(vec![
+                    Vec2::new(100.0, 500.0),
+                    Vec2::new(200.0, 450.0),
+                    Vec2::new(300.0, 480.0),
+                    Vec2::new(400.0, 420.0),
+                    Vec2::new(500.0, 500.0),
+                ])
+                .unwrap(),
+            ),
+            bumps: ColliderShape::Heightfield(bumps),
+            arch: ColliderShape::TriMesh(arch),
             colliding: Vec::new(),
         }
     }
@@ -41,19 +84,39 @@ impl Demo {
         let offset_vec = Vec2::new(0.0, 50.0);
         self.circle2.update_position(mouse_pos - offset_vec);
         self.box2.update_position(mouse_pos + offset_vec);
-        let shapes = [self.circle1, self.box1, self.circle2, self.box2];
+        let shapes = [
+            &self.circle1,
+            &self.box1,
+            &self.hexagon,
+            &self.capsule,
+            &self.hills,
+            &self.bumps,
+            &self.arch,
+            &self.circle2,
+            &self.box2,
+        ];
         for (i, shape1) in shapes.into_iter().enumerate() {
             for shape2 in shapes.into_iter().skip(i + 1) {
                 if shape1.test_collision(shape2).is_some() {
-                    self.colliding.extend([shape1, shape2]);
+                    self.colliding.extend([shape1.clone(), shape2.clone()]);
                 }
             }
         }
     }
 
     pub fn draw(&self) {
-        for shape in [self.circle1, self.circle2, self.box1, self.box2] {
-            let color = if self.colliding.contains(&shape) {
+        for shape in [
+            &self.circle1,
+            &self.circle2,
+            &self.box1,
+            &self.box2,
+            &self.hexagon,
+            &self.capsule,
+            &self.hills,
+            &self.bumps,
+            &self.arch,
+        ] {
+            let color = if self.colliding.contains(shape) {
                 GREEN
             } else {
                 RED
@@ -72,6 +135,55 @@ impl Demo {
                         color,
                     );
                 }
+                ColliderShape::Polygon(p) => {
+                    let center = p.centroid();
+                    for i in 0..p.vertex_count() {
+                        let v1 = p.vertex(i);
+                        let v2 = p.vertex((i + 1) % p.vertex_count());
+                        draw_triangle(
+                            vec2(center.x, center.y),
+                            vec2(v1.x, v1.y),
+                            vec2(v2.x, v2.y),
+                            color,
+                        );
+                    }
+                }
+                ColliderShape::Capsule(c) => {
+                    let segment = c.segment();
+                    let (start, end) = (segment.start, segment.end);
+                    draw_line(start.x, start.y, end.x, end.y, c.radius * 2.0, color);
+                    draw_circle(start.x, start.y, c.radius, color);
+                    draw_circle(end.x, end.y, c.radius, color);
+                }
+                ColliderShape::Segment(s) => {
+                    let line = s.line();
+                    draw_line(
+                        line.start.x,
+                        line.start.y,
+                        line.end.x,
+                        line.end.y,
+                        2.0,
+                        color,
+                    );
+                }
+                ColliderShape::Polyline(p) => {
+                    for segment in p.segments() {
+                        let (start, end) = (segment.line.start, segment.line.end);
+                        draw_line(start.x, start.y, end.x, end.y, 2.0, color);
+                    }
+                }
+                ColliderShape::TriMesh(m) => {
+                    for triangle in m.triangles() {
+                        let [v1, v2, v3] = [0, 1, 2].map(|i| triangle.vertex(i));
+                        draw_triangle(vec2(v1.x, v1.y), vec2(v2.x, v2.y), vec2(v3.x, v3.y), color);
+                    }
+                }
+                ColliderShape::Heightfield(h) => {
+                    for i in 0..h.cell_count() {
+                        let (start, end) = (h.point(i), h.point(i + 1));
+                        draw_line(start.x, start.y, end.x, end.y, 2.0, color);
+                    }
+                }
             }
         }
     }
@@ -85,6 +197,7 @@ impl Demo {
     }
 }
 
+#[allow(dead_code)]
 struct Wind {
     speed: Vec2,
 }
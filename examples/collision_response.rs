@@ -49,7 +49,7 @@ impl Demo {
         force_registry.insert(circle1_handle, right_wind_handle);
         force_registry.insert(circle2_handle, left_wind_handle);
         let shape = ColliderShape::Circle(Circle::new(Vec2::ZERO, CIRCLE_RADIUS));
-        let coll1 = Collider::new(shape);
+        let coll1 = Collider::new(shape.clone());
         let coll2 = Collider::new(shape);
         colliders.insert_with_parent(coll1, circle1_handle, &mut bodies);
         colliders.insert_with_parent(coll2, circle2_handle, &mut bodies);
@@ -91,11 +91,34 @@ impl Demo {
         force_registry.insert(box1_handle, right_wind_handle);
         force_registry.insert(box2_handle, left_wind_handle);
         let shape = ColliderShape::Box2D(Box2D::new(Vec2::ZERO, Vec2::splat(BOX_SIZE), 0.0));
-        let coll1 = Collider::new(shape);
+        let coll1 = Collider::new(shape.clone());
         let coll2 = Collider::new(shape);
         colliders.insert_with_parent(coll1, box1_handle, &mut bodies);
         colliders.insert_with_parent(coll2, box2_handle, &mut bodies);
 
+        // Compound (L-shape) vs Circle
+
+        let position = Vec2::new(12.0, 25.0);
+        let mut compound = RigidBody::new(position, 0.0);
+        compound.set_mass(5.0);
+        let compound_handle = bodies.insert(compound);
+        let position = Vec2::new(25.0, 25.0);
+        let mut circle = RigidBody::new(position, 0.0);
+        circle.set_mass(10.0);
+        let circle_handle = bodies.insert(circle);
+        force_registry.insert(compound_handle, right_wind_handle);
+        force_registry.insert(circle_handle, left_wind_handle);
+        let shape = ColliderShape::Box2D(Box2D::new(Vec2::ZERO, Vec2::splat(BOX_SIZE), 0.0));
+        for local_position in [Vec2::ZERO, Vec2::new(0.0, BOX_SIZE), Vec2::splat(BOX_SIZE)] {
+            let coll = Collider::with_local_transform(shape.clone(), local_position, 0.0);
+            colliders.insert_with_parent(coll, compound_handle, &mut bodies);
+        }
+        let coll = Collider::new(ColliderShape::Circle(Circle::new(
+            Vec2::ZERO,
+            CIRCLE_RADIUS,
+        )));
+        colliders.insert_with_parent(coll, circle_handle, &mut bodies);
+
         Self {
             pipeline,
             bodies,
@@ -119,26 +142,76 @@ impl Demo {
 
     pub fn draw(&self) {
         for (i, (_, body)) in self.bodies.iter().enumerate() {
-            let collider = &self.colliders[body.collider().unwrap()];
             let color = COLORS[i % 2];
-            match collider.shape {
-                ColliderShape::Circle(c) => draw_circle(
-                    c.center.x * 16.0,
-                    c.center.y * 16.0,
-                    CIRCLE_RADIUS * 16.0,
-                    color,
-                ),
-                ColliderShape::Box2D(b) => {
-                    let radius = b.half_size().length();
-                    let bcenter = b.center();
-                    draw_poly(
-                        bcenter.x * 16.0,
-                        bcenter.y * 16.0,
-                        4,
-                        radius * 16.0,
-                        45.0 - b.rotation.to_degrees(),
+            for collider in body.colliders().iter().map(|h| &self.colliders[*h]) {
+                match &collider.shape {
+                    ColliderShape::Circle(c) => draw_circle(
+                        c.center.x * 16.0,
+                        c.center.y * 16.0,
+                        CIRCLE_RADIUS * 16.0,
                         color,
-                    );
+                    ),
+                    ColliderShape::Box2D(b) => {
+                        let radius = b.half_size().length();
+                        let bcenter = b.center();
+                        draw_poly(
+                            bcenter.x * 16.0,
+                            bcenter.y * 16.0,
+                            4,
+                            radius * 16.0,
+                            45.0 - b.rotation.to_degrees(),
+                            color,
+                        );
+                    }
+                    ColliderShape::Polygon(p) => {
+                        let center = p.centroid() * 16.0;
+                        for i in 0..p.vertex_count() {
+                            let v1 = p.vertex(i) * 16.0;
+                            let v2 = p.vertex((i + 1) % p.vertex_count()) * 16.0;
+                            draw_triangle(
+                                vec2(center.x, center.y),
+                                vec2(v1.x, v1.y),
+                                vec2(v2.x, v2.y),
+                                color,
+                            );
+                        }
+                    }
+                    ColliderShape::Capsule(c) => {
+                        let segment = c.segment();
+                        let (start, end) = (segment.start * 16.0, segment.end * 16.0);
+                        let radius = c.radius * 16.0;
+                        draw_line(start.x, start.y, end.x, end.y, radius * 2.0, color);
+                        draw_circle(start.x, start.y, radius, color);
+                        draw_circle(end.x, end.y, radius, color);
+                    }
+                    ColliderShape::Segment(s) => {
+                        let line = s.line();
+                        let (start, end) = (line.start * 16.0, line.end * 16.0);
+                        draw_line(start.x, start.y, end.x, end.y, 2.0, color);
+                    }
+                    ColliderShape::Polyline(p) => {
+                        for segment in p.segments() {
+                            let (start, end) = (segment.line.start * 16.0, segment.line.end * 16.0);
+                            draw_line(start.x, start.y, end.x, end.y, 2.0, color);
+                        }
+                    }
+                    ColliderShape::TriMesh(m) => {
+                        for triangle in m.triangles() {
+                            let [v1, v2, v3] = [0, 1, 2].map(|i| triangle.vertex(i) * 16.0);
+                            draw_triangle(
+                                vec2(v1.x, v1.y),
+                                vec2(v2.x, v2.y),
+                                vec2(v3.x, v3.y),
+                                color,
+                            );
+                        }
+                    }
+                    ColliderShape::Heightfield(h) => {
+                        for i in 0..h.cell_count() {
+                            let (start, end) = (h.point(i) * 16.0, h.point(i + 1) * 16.0);
+                            draw_line(start.x, start.y, end.x, end.y, 2.0, color);
+                        }
+                    }
                 }
             }
         }
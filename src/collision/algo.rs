@@ -2,12 +2,15 @@ use approx::abs_diff_eq;
 use glam::Vec2;
 
 use crate::{
-    geometry::{Box2D, Convex},
+    geometry::{
+        Box2D, Capsule, ChainSegment, Convex, ConvexPolygon, Heightfield, Polyline, SupportMap,
+        TriMesh,
+    },
     math::{div_or_zero, Vec2Ext},
     Aabb, Circle, Line2D, Ray2D, RaycastResult2D,
 };
 
-use super::{collider::ColliderShape, CollisionManifold};
+use super::{collider::ColliderShape, CollisionManifold, ShapeCastResult2D};
 
 pub fn is_point_on_line(point: Vec2, line: Line2D) -> bool {
     // Line equation: y = mx + b
@@ -34,13 +37,29 @@ pub fn is_point_in_aabb(point: Vec2, aabb: Aabb) -> bool {
 }
 
 pub fn is_point_in_box2d(point: Vec2, box2d: Box2D) -> bool {
-    let rotated_point = Vec2::from_angle(box2d.rotation).rotate_around_point(point, box2d.center());
+    // Undo the rotation of the box so it can be treated as axis aligned
+    let rotated_point =
+        Vec2::from_angle(-box2d.rotation).rotate_around_point(point, box2d.center());
     rotated_point.x >= box2d.min.x
         && rotated_point.x <= box2d.max.x
         && rotated_point.y >= box2d.min.y
         && rotated_point.y <= box2d.max.y
 }
 
+pub fn is_point_in_polygon(point: Vec2, polygon: &ConvexPolygon) -> bool {
+    (0..polygon.vertex_count()).all(|i| polygon.normal(i).dot(point - polygon.vertex(i)) <= 0.0)
+}
+
+pub fn is_point_in_trimesh(point: Vec2, mesh: &TriMesh) -> bool {
+    let local_point = mesh.to_local(point);
+    let mut contains = false;
+    mesh.bvh().query_point(local_point, |_, i| {
+        contains = is_point_in_polygon(local_point, &mesh.local_triangles()[i]);
+        !contains
+    });
+    contains
+}
+
 pub fn line_circle(line: Line2D, circle: Circle) -> bool {
     if is_point_in_circle(line.start, circle) || is_point_in_circle(line.end, circle) {
         return true;
@@ -86,10 +105,19 @@ pub fn line_box2d(line: Line2D, box2d: Box2D) -> bool {
     line_aabb(rotated_line, Aabb::new(box2d.min, box2d.max))
 }
 
-pub fn raycast_shape(ray: Ray2D, collider: ColliderShape) -> Option<RaycastResult2D> {
+pub fn raycast_shape(ray: Ray2D, collider: &ColliderShape) -> Option<RaycastResult2D> {
     match collider {
-        ColliderShape::Circle(circle) => raycast_circle(ray, circle),
-        ColliderShape::Box2D(box2d) => raycast_box2d(ray, box2d),
+        ColliderShape::Circle(circle) => raycast_circle(ray, *circle),
+        ColliderShape::Box2D(box2d) => raycast_box2d(ray, *box2d),
+        ColliderShape::Polygon(polygon) => raycast_polygon(ray, polygon),
+        ColliderShape::Capsule(capsule) => raycast_capsule(ray, *capsule),
+        ColliderShape::Segment(segment) => raycast_segment(ray, segment.line()),
+        ColliderShape::Polyline(polyline) => polyline
+            .segments()
+            .filter_map(|segment| raycast_segment(ray, segment.line))
+            .min_by(|a, b| a.t.total_cmp(&b.t)),
+        ColliderShape::Heightfield(heightfield) => raycast_heightfield(ray, heightfield),
+        ColliderShape::TriMesh(mesh) => raycast_trimesh(ray, mesh),
     }
 }
 
@@ -113,87 +141,249 @@ pub fn raycast_circle(ray: Ray2D, circle: Circle) -> Option<RaycastResult2D> {
         a - f
     };
 
+    // The circle is behind the ray
+    if t < 0.0 {
+        return None;
+    }
+
     let point = ray.origin + (ray.direction * t);
     let normal = (point - circle.center).normalize_or_zero();
 
     Some(RaycastResult2D { point, normal, t })
 }
 
+// Rays starting inside the box hit it where they leave it
 pub fn raycast_aabb(ray: Ray2D, aabb: Aabb) -> Option<RaycastResult2D> {
-    let origin_to_max = aabb.max - ray.origin;
-    let origin_to_min = aabb.min - ray.origin;
-    let direction = ray.direction;
+    let mut t_min = f32::NEG_INFINITY;
+    let mut t_max = f32::INFINITY;
+    let mut normal_min = Vec2::ZERO;
+    let mut normal_max = Vec2::ZERO;
 
-    let step_x_to_max = div_or_zero(origin_to_max.x, direction.x);
-    let step_y_to_max = div_or_zero(origin_to_max.y, direction.y);
-    let step_x_to_min = div_or_zero(origin_to_min.x, direction.x);
-    let step_y_to_min = div_or_zero(origin_to_min.y, direction.y);
+    // Clip the ray against the slab between the two faces of each axis
+    for i in 0..2 {
+        if ray.direction[i] == 0.0 {
+            if ray.origin[i] < aabb.min[i] || ray.origin[i] > aabb.max[i] {
+                return None;
+            }
+            continue;
+        }
 
-    let t_min = f32::max(step_x_to_min, step_y_to_min);
-    let t_max = f32::min(step_x_to_max, step_y_to_max);
+        let mut axis = Vec2::ZERO;
+        axis[i] = 1.0;
+        let inv_direction = 1.0 / ray.direction[i];
+        let mut t1 = (aabb.min[i] - ray.origin[i]) * inv_direction;
+        let mut t2 = (aabb.max[i] - ray.origin[i]) * inv_direction;
+        let mut normal1 = -axis;
+        let mut normal2 = axis;
+        if t1 > t2 {
+            std::mem::swap(&mut t1, &mut t2);
+            std::mem::swap(&mut normal1, &mut normal2);
+        }
 
-    if t_max < 0.0 || t_min > t_max {
-        return None;
+        if t1 > t_min {
+            t_min = t1;
+            normal_min = normal1;
+        }
+        if t2 < t_max {
+            t_max = t2;
+            normal_max = normal2;
+        }
     }
 
-    let t = if t_min < 0.0 { t_max } else { t_min };
-    let hit = t > 0.0; // && t * t < ray.maximum
-    if !hit {
+    if t_max < 0.0 || t_min > t_max {
         return None;
     }
 
+    let (t, normal) = if t_min < 0.0 {
+        (t_max, normal_max)
+    } else {
+        (t_min, normal_min)
+    };
     let point = ray.origin + (ray.direction * t);
-    let normal = (ray.origin - point).normalize_or_zero();
     Some(RaycastResult2D { point, normal, t })
 }
 
 pub fn raycast_box2d(ray: Ray2D, box2d: Box2D) -> Option<RaycastResult2D> {
-    let rotation_vec = Vec2::from_angle(-box2d.rotation);
-    let x_axis = rotation_vec.rotate(Vec2::X);
-    let y_axis = rotation_vec.rotate(Vec2::Y);
-    let half_size = box2d.half_size();
-
-    let p = box2d.center() - ray.origin;
+    // Cast in the local space of the box, where it is axis aligned
+    let center = box2d.center();
+    let inv_rotation = Vec2::from_angle(-box2d.rotation);
+    let local_ray = Ray2D {
+        origin: inv_rotation.rotate(ray.origin - center),
+        direction: inv_rotation.rotate(ray.direction),
+    };
+    let local_aabb = Aabb::from_center_and_half_size(Vec2::ZERO, box2d.half_size());
+    let hit = raycast_aabb(local_ray, local_aabb)?;
 
-    // project the ray direction onto each axis of the box
-    let mut f = Vec2::new(x_axis.dot(ray.direction), y_axis.dot(ray.direction));
-    // project p onto each axis of the box
-    let e = Vec2::new(x_axis.dot(p), y_axis.dot(p));
+    let point = ray.origin + (ray.direction * hit.t);
+    let normal = Vec2::from_angle(box2d.rotation).rotate(hit.normal);
+    Some(RaycastResult2D {
+        point,
+        normal,
+        t: hit.t,
+    })
+}
 
-    let mut t_arr = [0.0; 4];
-    // loop over all axes
-    for i in 0..2 {
-        if abs_diff_eq!(f[i], 0.0) {
-            // if the ray is parallel to the current axis and the origin
-            // of the ray is not inside the box, we have no hit
-            if -e[i] - half_size[i] < 0.0 || -e[i] + half_size[i] > 0.0 {
+// Clips the ray against the half plane behind each edge. Rays starting inside
+// the polygon hit it where they leave it.
+pub fn raycast_polygon(ray: Ray2D, polygon: &ConvexPolygon) -> Option<RaycastResult2D> {
+    let mut t_min = f32::NEG_INFINITY;
+    let mut t_max = f32::INFINITY;
+    let mut normal_min = Vec2::ZERO;
+    let mut normal_max = Vec2::ZERO;
+    for i in 0..polygon.vertex_count() {
+        let normal = polygon.normal(i);
+        // Distance of the origin in front of the edge, and how fast the ray
+        // moves towards it
+        let distance = normal.dot(ray.origin - polygon.vertex(i));
+        let speed = normal.dot(ray.direction);
+        if speed == 0.0 {
+            if distance > 0.0 {
                 return None;
             }
-            f[i] = 0.00001;
+            continue;
+        }
+
+        let t = -distance / speed;
+        if speed < 0.0 {
+            // Entering through this edge
+            if t > t_min {
+                t_min = t;
+                normal_min = normal;
+            }
+        } else if t < t_max {
+            t_max = t;
+            normal_max = normal;
         }
 
-        t_arr[i * 2] = (e[i] + half_size[i]) / f[i]; // tmax for this axis
-        t_arr[i * 2 + 1] = (e[i] - half_size[i]) / f[i]; // tmin for this axis
+        if t_min > t_max {
+            return None;
+        }
     }
 
-    let t_min = f32::max(f32::min(t_arr[0], t_arr[1]), f32::min(t_arr[2], t_arr[3]));
-    let t_max = f32::min(f32::max(t_arr[0], t_arr[1]), f32::max(t_arr[2], t_arr[3]));
+    if t_max < 0.0 {
+        return None;
+    }
 
-    if t_max < 0.0 || t_min > t_max {
+    let (t, normal) = if t_min < 0.0 {
+        (t_max, normal_max)
+    } else {
+        (t_min, normal_min)
+    };
+    let point = ray.origin + (ray.direction * t);
+    Some(RaycastResult2D { point, normal, t })
+}
+
+// Rays starting inside the capsule hit it where they leave it
+pub fn raycast_capsule(ray: Ray2D, capsule: Capsule) -> Option<RaycastResult2D> {
+    let segment = capsule.segment();
+    if closest_point_on_segment(ray.origin, segment).distance_squared(ray.origin)
+        <= capsule.radius * capsule.radius
+    {
+        // Come back from a point past the far side of the capsule
+        let distance = ray.origin.distance(segment.start) + segment.length() + capsule.radius + 1.0;
+        let back_ray = Ray2D::new(ray.origin + ray.direction * distance, -ray.direction);
+        let hit = raycast_capsule(back_ray, capsule)?;
+        return Some(RaycastResult2D {
+            t: distance - hit.t,
+            ..hit
+        });
+    }
+
+    // Outside the capsule the first hit on either cap or the box between them
+    // is the hit on the capsule
+    let caps = [segment.start, segment.end]
+        .map(|center| raycast_circle(ray, Circle::new(center, capsule.radius)));
+    let side = segment.to_vec2().perp().normalize_or_zero() * capsule.radius;
+    let middle = ConvexPolygon::new(&[
+        segment.start - side,
+        segment.end - side,
+        segment.end + side,
+        segment.start + side,
+    ])
+    .ok()
+    .and_then(|middle| raycast_polygon(ray, &middle));
+    caps.into_iter()
+        .chain([middle])
+        .flatten()
+        .min_by(|a, b| a.t.total_cmp(&b.t))
+}
+
+// The normal faces the ray, whichever side of the segment it comes from
+pub fn raycast_segment(ray: Ray2D, segment: Line2D) -> Option<RaycastResult2D> {
+    let edge = segment.to_vec2();
+    let denominator = ray.direction.perp_dot(edge);
+    if denominator == 0.0 {
         return None;
     }
 
-    let t = if t_min < 0.0 { t_max } else { t_min };
-    let hit = t > 0.0; // && t * t < ray.maximum
-    if !hit {
+    let origin_to_start = segment.start - ray.origin;
+    let t = origin_to_start.perp_dot(edge) / denominator;
+    let s = origin_to_start.perp_dot(ray.direction) / denominator;
+    if t < 0.0 || !(0.0..=1.0).contains(&s) {
         return None;
     }
 
+    let mut normal = edge.perp().normalize_or_zero();
+    if normal.dot(ray.direction) > 0.0 {
+        normal = -normal;
+    }
     let point = ray.origin + (ray.direction * t);
-    let normal = (ray.origin - point).normalize_or_zero();
     Some(RaycastResult2D { point, normal, t })
 }
 
+// Walks the cells along the ray, so the first hit is the closest one
+pub fn raycast_heightfield(ray: Ray2D, heightfield: &Heightfield) -> Option<RaycastResult2D> {
+    let cells = heightfield.cells_in_x_range(ray.origin.x, ray.origin.x);
+    let cells = if ray.direction.x > 0.0 {
+        cells.start..heightfield.cell_count()
+    } else if ray.direction.x < 0.0 {
+        0..cells.end
+    } else {
+        cells
+    };
+
+    let hit_cell = |i| raycast_segment(ray, heightfield.segment(i).line);
+    if ray.direction.x < 0.0 {
+        cells.rev().find_map(hit_cell)
+    } else {
+        cells.into_iter().find_map(hit_cell)
+    }
+}
+
+// Closest hit on any triangle of the mesh
+// Only the outline of the mesh is hit. Rays starting inside hit it where they
+// leave the mesh, not at the edges between its triangles.
+pub fn raycast_trimesh(ray: Ray2D, mesh: &TriMesh) -> Option<RaycastResult2D> {
+    let rotation_vec = Vec2::from_angle(mesh.rotation);
+    let local_ray = Ray2D::new(
+        mesh.to_local(ray.origin),
+        Vec2::from_angle(-mesh.rotation).rotate(ray.direction),
+    );
+
+    let mut closest: Option<RaycastResult2D> = None;
+    mesh.bvh().cast_ray(local_ray, f32::MAX, |_, i| {
+        let triangle = &mesh.local_triangles()[i];
+        for edge in (0..3).filter(|edge| mesh.is_boundary_edge(i, *edge)) {
+            let line = Line2D::new(triangle.vertex(edge), triangle.vertex((edge + 1) % 3));
+            let Some(hit) = raycast_segment(local_ray, line) else {
+                continue;
+            };
+            if closest.as_ref().is_none_or(|closest| hit.t < closest.t) {
+                closest = Some(RaycastResult2D {
+                    normal: triangle.normal(edge),
+                    ..hit
+                });
+            }
+        }
+        closest.as_ref().map_or(f32::MAX, |hit| hit.t)
+    });
+    closest.map(|hit| RaycastResult2D {
+        point: mesh.to_world(hit.point),
+        normal: rotation_vec.rotate(hit.normal),
+        t: hit.t,
+    })
+}
+
 pub fn circle_circle(c1: Circle, c2: Circle) -> Option<CollisionManifold> {
     let sum_radii = c1.radius + c2.radius;
     let c1_to_c2 = c2.center - c1.center;
@@ -216,26 +406,163 @@ pub fn circle_circle(c1: Circle, c2: Circle) -> Option<CollisionManifold> {
 
 pub fn box2d_circle(box2d: Box2D, circle: Circle) -> Option<CollisionManifold> {
     // Bring circle to local box2d rotation
-    let local_center =
-        Vec2::from_angle(box2d.rotation).rotate_around_point(circle.center, box2d.center());
-    let local_circle = Circle::new(local_center, circle.radius);
-
     let box2d_center = box2d.center();
-    let box2d_to_circle = local_circle.center - box2d_center;
+    let rotation_vec = Vec2::from_angle(box2d.rotation);
+    let box2d_to_circle = Vec2::from_angle(-box2d.rotation).rotate(circle.center - box2d_center);
+
     let half_size = box2d.half_size();
     let closest_point_on_box = box2d_to_circle.clamp(-half_size, half_size);
-    let local_point = box2d_to_circle - closest_point_on_box;
-    let distance_squared = local_point.length_squared();
-    if distance_squared > local_circle.radius * local_circle.radius {
+    let (local_normal, depth, closest_point_on_box) = if closest_point_on_box != box2d_to_circle {
+        let local_point = box2d_to_circle - closest_point_on_box;
+        let distance_squared = local_point.length_squared();
+        if distance_squared > circle.radius * circle.radius {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        let normal = local_point.normalize_or_zero();
+        (normal, circle.radius - distance, closest_point_on_box)
+    } else {
+        // The center of the circle is inside the box, push it out through the closest face
+        let distances_to_faces = half_size - box2d_to_circle.abs();
+        if distances_to_faces.x < distances_to_faces.y {
+            let normal = Vec2::new(box2d_to_circle.x.signum(), 0.0);
+            let face_point = Vec2::new(normal.x * half_size.x, box2d_to_circle.y);
+            (normal, distances_to_faces.x + circle.radius, face_point)
+        } else {
+            let normal = Vec2::new(0.0, box2d_to_circle.y.signum());
+            let face_point = Vec2::new(box2d_to_circle.x, normal.y * half_size.y);
+            (normal, distances_to_faces.y + circle.radius, face_point)
+        }
+    };
+
+    let normal = rotation_vec.rotate(local_normal);
+    let contact_point_a = box2d_center + rotation_vec.rotate(closest_point_on_box);
+    let contact_point_b = circle.center - normal * circle.radius;
+    Some(CollisionManifold {
+        normal,
+        contact_point_a,
+        contact_point_b,
+        depth,
+    })
+}
+
+pub fn polygon_circle(polygon: &ConvexPolygon, circle: Circle) -> Option<CollisionManifold> {
+    // Find the edge the center of the circle is furthest in front of
+    let mut separation = f32::MIN;
+    let mut edge = 0;
+    for i in 0..polygon.vertex_count() {
+        let distance = polygon.normal(i).dot(circle.center - polygon.vertex(i));
+        if distance > circle.radius {
+            return None;
+        }
+        if distance > separation {
+            separation = distance;
+            edge = i;
+        }
+    }
+
+    let v1 = polygon.vertex(edge);
+    let v2 = polygon.vertex((edge + 1) % polygon.vertex_count());
+    let (normal, closest_point, distance) = if separation <= 0.0 {
+        // The center is inside the polygon
+        let normal = polygon.normal(edge);
+        (normal, circle.center - normal * separation, separation)
+    } else if (circle.center - v1).dot(v2 - v1) <= 0.0 {
+        (
+            (circle.center - v1).normalize_or_zero(),
+            v1,
+            circle.center.distance(v1),
+        )
+    } else if (circle.center - v2).dot(v1 - v2) <= 0.0 {
+        (
+            (circle.center - v2).normalize_or_zero(),
+            v2,
+            circle.center.distance(v2),
+        )
+    } else {
+        let normal = polygon.normal(edge);
+        (normal, circle.center - normal * separation, separation)
+    };
+
+    if distance > circle.radius {
         return None;
     }
 
-    let normal = local_point.normalize_or_zero();
-    let normal = Vec2::from_angle(-box2d.rotation).rotate(normal);
-    let distance = distance_squared.sqrt();
-    let depth = circle.radius - distance;
-    let contact_point_a = box2d_center;
-    let contact_point_b = -normal * circle.radius;
+    Some(CollisionManifold {
+        normal,
+        contact_point_a: closest_point,
+        contact_point_b: circle.center - normal * circle.radius,
+        depth: circle.radius - distance,
+    })
+}
+
+// Picks the edge of least penetration as the reference face, then clips the
+// most opposed edge of the other polygon against its sides. The contact is in
+// the middle of the clipped points that are behind the reference face.
+pub fn polygon_polygon(p1: &ConvexPolygon, p2: &ConvexPolygon) -> Option<CollisionManifold> {
+    let (separation1, edge1) = max_separation(p1, p2);
+    if separation1 > 0.0 {
+        return None;
+    }
+    let (separation2, edge2) = max_separation(p2, p1);
+    if separation2 > 0.0 {
+        return None;
+    }
+
+    // Prefer the first polygon so the choice doesn't flicker between frames
+    const REFERENCE_TOLERANCE: f32 = 0.005;
+    let (reference, incident, reference_edge, flipped) =
+        if separation2 > separation1 + REFERENCE_TOLERANCE {
+            (p2, p1, edge2, true)
+        } else {
+            (p1, p2, edge1, false)
+        };
+
+    let reference_normal = reference.normal(reference_edge);
+    let incident_edge = (0..incident.vertex_count())
+        .min_by(|i, j| {
+            let dot_i = incident.normal(*i).dot(reference_normal);
+            let dot_j = incident.normal(*j).dot(reference_normal);
+            dot_i.total_cmp(&dot_j)
+        })
+        .unwrap_or(0);
+    let mut incident_points = [
+        incident.vertex(incident_edge),
+        incident.vertex((incident_edge + 1) % incident.vertex_count()),
+    ];
+
+    // Clip the incident edge to the sides of the reference edge
+    let v1 = reference.vertex(reference_edge);
+    let v2 = reference.vertex((reference_edge + 1) % reference.vertex_count());
+    let tangent = (v2 - v1).normalize_or_zero();
+    incident_points = clip_segment(incident_points, -tangent, -tangent.dot(v1))?;
+    incident_points = clip_segment(incident_points, tangent, tangent.dot(v2))?;
+
+    let mut depth = 0.0_f32;
+    let mut contact_sum = Vec2::ZERO;
+    let mut contact_count = 0.0;
+    for point in incident_points {
+        let separation = reference_normal.dot(point - v1);
+        if separation <= 0.0 {
+            depth = depth.max(-separation);
+            contact_sum += point;
+            contact_count += 1.0;
+        }
+    }
+    if contact_count == 0.0 {
+        return None;
+    }
+
+    let incident_contact = contact_sum / contact_count;
+    let reference_contact =
+        incident_contact - reference_normal * reference_normal.dot(incident_contact - v1);
+    // The normal points from the first polygon to the second
+    let (normal, contact_point_a, contact_point_b) = if flipped {
+        (-reference_normal, incident_contact, reference_contact)
+    } else {
+        (reference_normal, reference_contact, incident_contact)
+    };
     Some(CollisionManifold {
         normal,
         contact_point_a,
@@ -244,6 +571,300 @@ pub fn box2d_circle(box2d: Box2D, circle: Circle) -> Option<CollisionManifold> {
     })
 }
 
+// Largest distance of `p2` in front of an edge of `p1`, and that edge
+fn max_separation(p1: &ConvexPolygon, p2: &ConvexPolygon) -> (f32, usize) {
+    let mut best = (f32::MIN, 0);
+    for i in 0..p1.vertex_count() {
+        let normal = p1.normal(i);
+        let vertex = p1.vertex(i);
+        let separation = p2
+            .vertices()
+            .map(|point| normal.dot(point - vertex))
+            .fold(f32::MAX, f32::min);
+        if separation > best.0 {
+            best = (separation, i);
+        }
+    }
+    best
+}
+
+// Keeps the part of the segment where `normal.dot(point) <= offset`
+fn clip_segment(points: [Vec2; 2], normal: Vec2, offset: f32) -> Option<[Vec2; 2]> {
+    let distance0 = normal.dot(points[0]) - offset;
+    let distance1 = normal.dot(points[1]) - offset;
+    match (distance0 <= 0.0, distance1 <= 0.0) {
+        (true, true) => Some(points),
+        (false, false) => None,
+        (inside0, _) => {
+            let t = distance0 / (distance0 - distance1);
+            let intersection = points[0] + (points[1] - points[0]) * t;
+            if inside0 {
+                Some([points[0], intersection])
+            } else {
+                Some([intersection, points[1]])
+            }
+        }
+    }
+}
+
+pub fn capsule_circle(capsule: Capsule, circle: Circle) -> Option<CollisionManifold> {
+    let closest = closest_point_on_segment(circle.center, capsule.segment());
+    circle_circle(Circle::new(closest, capsule.radius), circle)
+}
+
+pub fn capsule_capsule(c1: Capsule, c2: Capsule) -> Option<CollisionManifold> {
+    let (point1, point2) = closest_points_on_segments(c1.segment(), c2.segment());
+    circle_circle(
+        Circle::new(point1, c1.radius),
+        Circle::new(point2, c2.radius),
+    )
+}
+
+// The normal points from the polygon to the capsule
+pub fn polygon_capsule(polygon: &ConvexPolygon, capsule: Capsule) -> Option<CollisionManifold> {
+    let segment = capsule.segment();
+    // A segment through the polygon can have both ends outside of it. Its
+    // closest points are then where it crosses an edge, which would look like
+    // the capsule was barely touching.
+    let mut segment_inside =
+        is_point_in_polygon(segment.start, polygon) || is_point_in_polygon(segment.end, polygon);
+
+    // While the segment stays outside the polygon the closest points between
+    // them give the contact, like for a circle
+    let mut closest = (f32::MAX, Vec2::ZERO, Vec2::ZERO);
+    for i in 0..polygon.vertex_count() {
+        let edge = Line2D::new(
+            polygon.vertex(i),
+            polygon.vertex((i + 1) % polygon.vertex_count()),
+        );
+        segment_inside |= segments_cross(segment, edge);
+        let (on_segment, on_polygon) = closest_points_on_segments(segment, edge);
+        let distance = on_segment.distance(on_polygon);
+        if distance < closest.0 {
+            closest = (distance, on_segment, on_polygon);
+        }
+    }
+
+    let (distance, on_segment, on_polygon) = closest;
+    if !segment_inside && distance > capsule.radius {
+        return None;
+    }
+    if !segment_inside && distance > f32::EPSILON {
+        let normal = (on_segment - on_polygon) / distance;
+        return Some(CollisionManifold {
+            normal,
+            contact_point_a: on_polygon,
+            contact_point_b: on_segment - normal * capsule.radius,
+            depth: capsule.radius - distance,
+        });
+    }
+
+    // The segment is inside, push the capsule out along the axis of least
+    // penetration. Either a polygon edge...
+    let mut separation = f32::MIN;
+    let mut manifold = None;
+    for i in 0..polygon.vertex_count() {
+        let normal = polygon.normal(i);
+        let vertex = polygon.vertex(i);
+        let (edge_separation, deepest) = [segment.start, segment.end]
+            .into_iter()
+            .map(|point| (normal.dot(point - vertex), point))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap();
+        if edge_separation > separation {
+            separation = edge_separation;
+            manifold = Some(CollisionManifold {
+                normal,
+                contact_point_a: deepest - normal * edge_separation,
+                contact_point_b: deepest - normal * capsule.radius,
+                depth: capsule.radius - edge_separation,
+            });
+        }
+    }
+
+    // ...or one of the flat sides of the capsule
+    let side_normal = segment.to_vec2().perp().normalize_or_zero();
+    if side_normal != Vec2::ZERO {
+        for side in [side_normal, -side_normal] {
+            let (side_separation, deepest) = polygon
+                .vertices()
+                .map(|vertex| (side.dot(vertex - segment.start), vertex))
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .unwrap();
+            if side_separation > separation {
+                separation = side_separation;
+                let depth = capsule.radius - side_separation;
+                manifold = Some(CollisionManifold {
+                    normal: -side,
+                    contact_point_a: deepest,
+                    contact_point_b: deepest + side * depth,
+                    depth,
+                });
+            }
+        }
+    }
+
+    manifold
+}
+
+// The normal points from the polyline to the shape. Each segment only collides
+// with shapes whose center is in front of it. The contact with the deepest
+// segment is used.
+//
+// Normals at the ends of a segment come from the corner, unless the ghost
+// vertex says the corner is flat or hollow. Then the face normal is used
+// instead, so shapes sliding along the polyline don't catch on the inner
+// corners between its segments.
+pub fn polyline_shape(polyline: &Polyline, shape: &ColliderShape) -> Option<CollisionManifold> {
+    chain_shape(polyline.segments(), shape)
+}
+
+// Like `polyline_shape`, but only the cells below the shape are looked at
+pub fn heightfield_shape(
+    heightfield: &Heightfield,
+    shape: &ColliderShape,
+) -> Option<CollisionManifold> {
+    let aabb = shape.compute_aabb();
+    chain_shape(
+        heightfield.segments_in_x_range(aabb.min.x, aabb.max.x),
+        shape,
+    )
+}
+
+fn chain_shape(
+    segments: impl Iterator<Item = ChainSegment>,
+    shape: &ColliderShape,
+) -> Option<CollisionManifold> {
+    let aabb = shape.compute_aabb();
+    let center = shape.center();
+    segments
+        .filter(|segment| {
+            Aabb::from_points([segment.line.start, segment.line.end]).intersects(aabb)
+                && segment.faces(center)
+        })
+        .filter_map(|segment| chain_segment_shape(segment, shape))
+        .max_by(|a, b| a.depth.total_cmp(&b.depth))
+}
+
+fn chain_segment_shape(segment: ChainSegment, shape: &ColliderShape) -> Option<CollisionManifold> {
+    let line = segment.line;
+    let manifold =
+        ColliderShape::Capsule(Capsule::new(line.start, line.end, 0.0)).test_collision(shape)?;
+
+    let face_normal = segment.normal();
+    const FACE_TOLERANCE: f32 = 1e-4;
+    if manifold.normal.dot(face_normal) >= 1.0 - FACE_TOLERANCE {
+        return Some(manifold);
+    }
+
+    // The normal leans towards the end of the segment the contact is at. Only
+    // a convex corner may push along a normal between its two faces.
+    let edge_normal = |start: Vec2, end: Vec2| {
+        let edge = end - start;
+        Vec2::new(edge.y, -edge.x).normalize_or_zero()
+    };
+    let between = |from: Vec2, normal: Vec2, to: Vec2| {
+        from.perp_dot(to) > 0.0 && from.perp_dot(normal) >= 0.0 && normal.perp_dot(to) >= 0.0
+    };
+    let admissible = if manifold.normal.dot(line.to_vec2()) < 0.0 {
+        segment.previous.is_none_or(|previous| {
+            let previous_normal = edge_normal(previous, line.start);
+            between(previous_normal, manifold.normal, face_normal)
+        })
+    } else {
+        segment.next.is_none_or(|next| {
+            let next_normal = edge_normal(line.end, next);
+            between(face_normal, manifold.normal, next_normal)
+        })
+    };
+    if admissible {
+        return Some(manifold);
+    }
+
+    // Push the shape out along the face normal instead
+    let deepest = shape.support_point(-face_normal);
+    let separation = face_normal.dot(deepest - line.start);
+    if separation >= 0.0 {
+        return None;
+    }
+    Some(CollisionManifold {
+        normal: face_normal,
+        contact_point_a: deepest - face_normal * separation,
+        contact_point_b: deepest,
+        depth: -separation,
+    })
+}
+
+// The normal points from the mesh to the shape. The contact with the deepest
+// triangle is used.
+pub fn trimesh_shape(mesh: &TriMesh, shape: &ColliderShape) -> Option<CollisionManifold> {
+    let mut deepest: Option<CollisionManifold> = None;
+    mesh.triangles_in_aabb(shape.compute_aabb(), |i| {
+        let triangle = ColliderShape::Polygon(mesh.triangle(i));
+        if let Some(manifold) = triangle.test_collision(shape) {
+            if deepest
+                .as_ref()
+                .is_none_or(|deepest| manifold.depth > deepest.depth)
+            {
+                deepest = Some(manifold);
+            }
+        }
+        true
+    });
+    deepest
+}
+
+pub fn closest_point_on_segment(point: Vec2, segment: Line2D) -> Vec2 {
+    let direction = segment.to_vec2();
+    let t = div_or_zero(
+        (point - segment.start).dot(direction),
+        direction.length_squared(),
+    );
+    segment.start + direction * t.clamp(0.0, 1.0)
+}
+
+// Closest points between two segments, one on each (from Real-Time Collision
+// Detection by Christer Ericson)
+pub fn closest_points_on_segments(s1: Line2D, s2: Line2D) -> (Vec2, Vec2) {
+    let d1 = s1.to_vec2();
+    let d2 = s2.to_vec2();
+    let r = s1.start - s2.start;
+    let a = d1.length_squared();
+    let e = d2.length_squared();
+    let f = d2.dot(r);
+    if a <= f32::EPSILON {
+        return (s1.start, closest_point_on_segment(s1.start, s2));
+    }
+    if e <= f32::EPSILON {
+        return (closest_point_on_segment(s2.start, s1), s2.start);
+    }
+
+    let c = d1.dot(r);
+    let b = d1.dot(d2);
+    let denominator = a * e - b * b;
+    // Parallel segments get an arbitrary point
+    let mut s = if denominator > f32::EPSILON {
+        ((b * f - c * e) / denominator).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let mut t = (b * s + f) / e;
+    if t < 0.0 {
+        t = 0.0;
+        s = (-c / a).clamp(0.0, 1.0);
+    } else if t > 1.0 {
+        t = 1.0;
+        s = ((b - c) / a).clamp(0.0, 1.0);
+    }
+    (s1.start + d1 * s, s2.start + d2 * t)
+}
+
+// Whether the segments cross each other, not counting touching ends
+fn segments_cross(a: Line2D, b: Line2D) -> bool {
+    let side = |line: Line2D, point: Vec2| line.to_vec2().perp_dot(point - line.start);
+    side(a, b.start) * side(a, b.end) < 0.0 && side(b, a.start) * side(b, a.end) < 0.0
+}
+
 pub fn aabb_aabb_test(rect1: Aabb, rect2: Aabb) -> bool {
     let delta = rect2.center() - rect1.center();
     let total_size = rect1.half_size() + rect2.half_size();
@@ -281,38 +902,246 @@ pub fn aabb_aabb(b1: Aabb, b2: Aabb) -> Option<CollisionManifold> {
     })
 }
 
+// Clipping the boxes against each other like polygons gives a contact point
+// between them, which the solver needs to turn the boxes
 pub fn box2d_box2d(b1: Box2D, b2: Box2D) -> Option<CollisionManifold> {
-    let rotation_vec1 = Vec2::from_angle(b1.rotation);
-    let b1_axes = [rotation_vec1.rotate(Vec2::X), rotation_vec1.rotate(Vec2::Y)];
+    polygon_polygon(&b1.into(), &b2.into())
+}
 
-    let rotation_vec2 = Vec2::from_angle(b2.rotation);
-    let b2_axes = [rotation_vec2.rotate(Vec2::X), rotation_vec2.rotate(Vec2::Y)];
+// ==========================================
+// Shape casts
+// ==========================================
 
-    let mut best_axis = b1_axes[0];
-    let mut depth = f32::MAX;
+// In all of these the first shape moves by `velocity` per unit of time and the
+// second one stays in place.
 
-    for axis in b1_axes.into_iter().chain(b2_axes.into_iter()) {
-        let interval1 = get_interval(b1, axis);
-        let interval2 = get_interval(b2, axis);
-        if interval1.y < interval2.x || interval1.x > interval2.y {
-            return None;
+pub fn cast_circle_circle(
+    c1: Circle,
+    velocity: Vec2,
+    c2: Circle,
+    max_toi: f32,
+) -> Option<ShapeCastResult2D> {
+    let sum_radii = c1.radius + c2.radius;
+    if c1.center.distance_squared(c2.center) <= sum_radii * sum_radii {
+        let normal = (c1.center - c2.center).normalize_or_zero();
+        let point = c2.center + normal * c2.radius;
+        return Some(ShapeCastResult2D {
+            point,
+            normal,
+            toi: 0.0,
+        });
+    }
+
+    // Sweeping a circle against a circle is casting its center against a
+    // circle with both radii
+    let speed = velocity.length();
+    let ray = Ray2D::new(c1.center, velocity);
+    let hit = raycast_circle(ray, Circle::new(c2.center, sum_radii))?;
+    let toi = hit.t / speed;
+    if toi > max_toi {
+        return None;
+    }
+
+    Some(ShapeCastResult2D {
+        point: c2.center + hit.normal * c2.radius,
+        normal: hit.normal,
+        toi,
+    })
+}
+
+pub fn cast_circle_box2d(
+    circle: Circle,
+    velocity: Vec2,
+    box2d: Box2D,
+    max_toi: f32,
+) -> Option<ShapeCastResult2D> {
+    if let Some(manifold) = box2d_circle(box2d, circle) {
+        return Some(ShapeCastResult2D {
+            point: manifold.contact_point_a,
+            normal: manifold.normal,
+            toi: 0.0,
+        });
+    }
+
+    let speed = velocity.length();
+    if speed == 0.0 {
+        return None;
+    }
+
+    // Cast the center of the circle against the box grown by the radius, with
+    // rounded corners. Done in the local space of the box.
+    let box2d_center = box2d.center();
+    let rotation_vec = Vec2::from_angle(box2d.rotation);
+    let inv_rotation_vec = Vec2::from_angle(-box2d.rotation);
+    let ray = Ray2D {
+        origin: inv_rotation_vec.rotate(circle.center - box2d_center),
+        direction: inv_rotation_vec.rotate(velocity / speed),
+    };
+    let half_size = box2d.half_size();
+    let grown_box = Aabb::from_center_and_half_size(Vec2::ZERO, half_size + circle.radius);
+
+    let corner_hit = |corner: Vec2| {
+        raycast_circle(ray, Circle::new(corner, circle.radius))
+            .map(|hit| (corner, hit.normal, hit.t))
+    };
+    let (local_point, local_normal, t) = if grown_box.contains_point(ray.origin) {
+        // Already inside the grown box, next to one of the corners
+        corner_hit(ray.origin.clamp(-half_size, half_size))?
+    } else {
+        let hit = raycast_aabb(ray, grown_box)?;
+        if hit.point.x.abs() <= half_size.x || hit.point.y.abs() <= half_size.y {
+            (hit.point - hit.normal * circle.radius, hit.normal, hit.t)
+        } else {
+            corner_hit(hit.point.clamp(-half_size, half_size))?
         }
+    };
+
+    let toi = t / speed;
+    if toi > max_toi {
+        return None;
+    }
 
-        let overlap = f32::min(interval1.y, interval2.y) - f32::max(interval1.x, interval2.x);
-        if overlap < depth {
-            depth = overlap;
-            best_axis = axis;
+    Some(ShapeCastResult2D {
+        point: box2d_center + rotation_vec.rotate(local_point),
+        normal: rotation_vec.rotate(local_normal),
+        toi,
+    })
+}
+
+pub fn cast_box2d_circle(
+    box2d: Box2D,
+    velocity: Vec2,
+    circle: Circle,
+    max_toi: f32,
+) -> Option<ShapeCastResult2D> {
+    // Moving the box towards the circle is moving the circle away from the box
+    let hit = cast_circle_box2d(circle, -velocity, box2d, max_toi)?;
+    Some(ShapeCastResult2D {
+        point: hit.point + velocity * hit.toi,
+        normal: -hit.normal,
+        toi: hit.toi,
+    })
+}
+
+// Sweep version of the separating axis test. Along each axis the projections
+// of the shapes overlap during one interval of time, and the shapes touch
+// when all of those intervals overlap.
+pub fn cast_convex_convex<const N: usize, const M: usize>(
+    shape1: impl Convex<N>,
+    velocity: Vec2,
+    shape2: impl Convex<M>,
+    max_toi: f32,
+) -> Option<ShapeCastResult2D> {
+    let vertices1 = shape1.get_vertices();
+    let vertices2 = shape2.get_vertices();
+
+    let mut t_first = f32::NEG_INFINITY;
+    let mut t_last = f32::INFINITY;
+    let mut normal = Vec2::ZERO;
+    // Used if the shapes already overlap
+    let mut min_depth = f32::MAX;
+    let mut min_depth_normal = Vec2::ZERO;
+    for axis in candidate_axes(vertices1).chain(candidate_axes(vertices2)) {
+        let interval1 = project_vertices(&vertices1, axis);
+        let interval2 = project_vertices(&vertices2, axis);
+        let speed = velocity.dot(axis);
+        let (enter, exit, axis_normal) = if interval1.y < interval2.x {
+            if speed <= 0.0 {
+                return None;
+            }
+            let enter = (interval2.x - interval1.y) / speed;
+            let exit = (interval2.y - interval1.x) / speed;
+            (enter, exit, -axis)
+        } else if interval1.x > interval2.y {
+            if speed >= 0.0 {
+                return None;
+            }
+            let enter = (interval2.y - interval1.x) / speed;
+            let exit = (interval2.x - interval1.y) / speed;
+            (enter, exit, axis)
+        } else {
+            let depth_towards_negative = interval1.y - interval2.x;
+            let depth_towards_positive = interval2.y - interval1.x;
+            if depth_towards_negative.min(depth_towards_positive) < min_depth {
+                min_depth = depth_towards_negative.min(depth_towards_positive);
+                min_depth_normal = if depth_towards_negative < depth_towards_positive {
+                    -axis
+                } else {
+                    axis
+                };
+            }
+
+            let exit = if speed > 0.0 {
+                depth_towards_positive / speed
+            } else if speed < 0.0 {
+                -depth_towards_negative / speed
+            } else {
+                f32::INFINITY
+            };
+            (f32::NEG_INFINITY, exit, Vec2::ZERO)
+        };
+
+        if enter > t_first {
+            t_first = enter;
+            normal = axis_normal;
+        }
+        t_last = t_last.min(exit);
+        if t_first > t_last || t_first > max_toi {
+            return None;
         }
     }
 
-    Some(CollisionManifold {
-        normal: best_axis,
-        contact_point_a: b1.center(),
-        contact_point_b: b2.center(),
-        depth,
+    let (toi, normal) = if t_first == f32::NEG_INFINITY {
+        (0.0, min_depth_normal)
+    } else {
+        (t_first, normal)
+    };
+
+    // The contact is in the middle of where the features of both shapes that
+    // face each other overlap
+    let moved_vertices1 = vertices1.map(|vertex| vertex + velocity * toi);
+    let tangent = normal.perp();
+    let (_, range1) = support_feature(moved_vertices1, -normal, tangent);
+    let (distance2, range2) = support_feature(vertices2, normal, tangent);
+    let middle = (range1.x.max(range2.x) + range1.y.min(range2.y)) / 2.0;
+    Some(ShapeCastResult2D {
+        point: normal * distance2 + tangent * middle,
+        normal,
+        toi,
     })
 }
 
+// Every direction perpendicular to a pair of vertices. Covers the faces of the
+// convex hull no matter in which order the vertices are listed.
+fn candidate_axes<const N: usize>(vertices: [Vec2; N]) -> impl Iterator<Item = Vec2> {
+    (0..N)
+        .flat_map(move |i| (i + 1..N).map(move |j| (vertices[j] - vertices[i]).perp()))
+        .filter_map(|axis| axis.try_normalize())
+}
+
+// How far the shape reaches along `direction`, and the range along `tangent`
+// covered by the vertices that reach that far
+fn support_feature<const N: usize>(
+    vertices: [Vec2; N],
+    direction: Vec2,
+    tangent: Vec2,
+) -> (f32, Vec2) {
+    const FEATURE_TOLERANCE: f32 = 1e-3;
+
+    let distance = vertices
+        .iter()
+        .map(|vertex| vertex.dot(direction))
+        .fold(f32::MIN, f32::max);
+    let range = vertices
+        .iter()
+        .filter(|vertex| vertex.dot(direction) >= distance - FEATURE_TOLERANCE)
+        .map(|vertex| vertex.dot(tangent))
+        .fold(Vec2::new(f32::MAX, f32::MIN), |range, projection| {
+            Vec2::new(range.x.min(projection), range.y.max(projection))
+        });
+    (distance, range)
+}
+
 // ==========================================
 // SAT helpers
 // ==========================================
@@ -328,12 +1157,14 @@ pub fn box2d_box2d(b1: Box2D, b2: Box2D) -> Option<CollisionManifold> {
 // }
 
 pub fn get_interval<const N: usize>(shape: impl Convex<N>, axis: Vec2) -> Vec2 {
-    let vertices = shape.get_vertices();
+    project_vertices(&shape.get_vertices(), axis)
+}
 
+fn project_vertices(vertices: &[Vec2], axis: Vec2) -> Vec2 {
     let mut min = axis.dot(vertices[0]);
     let mut max = min;
     for vertex in vertices {
-        let projection = axis.dot(vertex);
+        let projection = axis.dot(*vertex);
         if projection < min {
             min = projection;
         } else if projection > max {
@@ -351,6 +1182,7 @@ mod tests {
     use glam::Vec2;
 
     use super::*;
+    use crate::Segment;
 
     #[test]
     fn test_is_point_on_line() {
@@ -386,6 +1218,18 @@ mod tests {
         assert!(!is_point_in_aabb(point, aabb));
     }
 
+    #[test]
+    fn point_in_box2d_follows_rotation() {
+        let rotation = Vec2::from_angle(PI / 6.0);
+        let box2d = Box2D::new(Vec2::ZERO, Vec2::new(4.0, 2.0), PI / 6.0);
+        // Along the long side of the box, which turning it the other way
+        // would miss
+        let point = box2d.center() + rotation.rotate(Vec2::new(1.8, 0.0));
+        assert!(is_point_in_box2d(point, box2d));
+        let point = box2d.center() + rotation.rotate(Vec2::new(0.0, 1.2));
+        assert!(!is_point_in_box2d(point, box2d));
+    }
+
     #[test]
     fn test_is_point_in_box2d() {
         let point = Vec2::new(1.2, 4.8);
@@ -478,4 +1322,501 @@ mod tests {
         let box2d = Box2D::new(Vec2::ZERO, Vec2::splat(50.0), PI / 4.0);
         assert!(box2d_circle(box2d, circle).is_none());
     }
+
+    #[test]
+    fn box2d_circle_normal_points_towards_circle() {
+        let box2d = Box2D::new(Vec2::ZERO, Vec2::splat(50.0), 0.0);
+        let circle = Circle::new(Vec2::new(25.0, 55.0), 10.0);
+        let manifold = box2d_circle(box2d, circle).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-6));
+        assert!((manifold.depth - 5.0).abs() < 1e-4);
+
+        // Center of the circle inside the box
+        let circle = Circle::new(Vec2::new(45.0, 25.0), 10.0);
+        let manifold = box2d_circle(box2d, circle).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::X, 1e-6));
+        assert!((manifold.depth - 15.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn box2d_circle_follows_box_rotation() {
+        let rotation = Vec2::from_angle(PI / 6.0);
+        let box2d = Box2D::new(Vec2::new(-2.0, -1.0), Vec2::new(2.0, 1.0), PI / 6.0);
+        // Above the long face in the frame of the box, which it would miss if
+        // the box was turned the other way
+        let circle = Circle::new(rotation.rotate(Vec2::new(1.5, 1.5)), 1.0);
+        let manifold = box2d_circle(box2d, circle).unwrap();
+        assert!(manifold.normal.abs_diff_eq(rotation.rotate(Vec2::Y), 1e-5));
+        assert!((manifold.depth - 0.5).abs() < 1e-5);
+        assert!(manifold
+            .contact_point_a
+            .abs_diff_eq(rotation.rotate(Vec2::new(1.5, 1.0)), 1e-5));
+        assert!(manifold
+            .contact_point_b
+            .abs_diff_eq(rotation.rotate(Vec2::new(1.5, 0.5)), 1e-5));
+    }
+
+    #[test]
+    fn box2d_box2d_contact_is_between_the_boxes() {
+        let ground = Box2D::new(Vec2::new(-10.0, -1.0), Vec2::new(10.0, 0.0), 0.0);
+        let b = Box2D::new(Vec2::new(6.5, -0.1), Vec2::new(7.5, 0.9), 0.0);
+        let manifold = box2d_box2d(ground, b).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-6));
+        assert!((manifold.depth - 0.1).abs() < 1e-5);
+        for point in [manifold.contact_point_a, manifold.contact_point_b] {
+            assert!((6.5..=7.5).contains(&point.x), "contact at {point}");
+            assert!((-0.1..=0.0).contains(&point.y), "contact at {point}");
+        }
+    }
+
+    #[test]
+    fn box2d_box2d_normal_points_from_first_to_second() {
+        let b1 = Box2D::new(Vec2::ZERO, Vec2::splat(10.0), 0.0);
+        let b2 = Box2D::new(Vec2::new(-8.0, 1.0), Vec2::new(2.0, 11.0), 0.0);
+        let manifold = box2d_box2d(b1, b2).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::NEG_X, 1e-6));
+        let manifold = box2d_box2d(b2, b1).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::X, 1e-6));
+    }
+
+    #[test]
+    fn ray_misses_circle_behind_it() {
+        let circle = Circle::new(Vec2::new(-5.0, 0.0), 1.0);
+        assert!(raycast_circle(Ray2D::new(Vec2::ZERO, Vec2::X), circle).is_none());
+        let hit = raycast_circle(Ray2D::new(Vec2::ZERO, Vec2::NEG_X), circle).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::X, 1e-6));
+    }
+
+    #[test]
+    fn raycast_aabb_reports_face_normal() {
+        let aabb = Aabb::new(Vec2::new(2.0, -1.0), Vec2::new(4.0, 1.0));
+        let hit = raycast_aabb(Ray2D::new(Vec2::new(0.0, 0.5), Vec2::X), aabb).unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-6));
+
+        // Starting inside, the ray hits the face it leaves through
+        let hit = raycast_aabb(Ray2D::new(Vec2::new(3.0, 0.0), Vec2::Y), aabb).unwrap();
+        assert!((hit.t - 1.0).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::Y, 1e-6));
+
+        assert!(raycast_aabb(Ray2D::new(Vec2::new(5.0, 0.0), Vec2::X), aabb).is_none());
+    }
+
+    #[test]
+    fn ray_starting_in_circle_hits_where_it_leaves() {
+        let circle = Circle::new(Vec2::ZERO, 1.0);
+        let hit = raycast_circle(Ray2D::new(Vec2::new(0.5, 0.0), Vec2::X), circle).unwrap();
+        assert!((hit.t - 0.5).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::X, 1e-6));
+    }
+
+    #[test]
+    fn slanted_ray_gets_aabb_face_normal() {
+        // The normal used to point back at the origin of the ray
+        let direction = Vec2::new(1.0, 0.25).normalize();
+        let aabb = Aabb::new(Vec2::new(2.0, -1.0), Vec2::new(4.0, 1.0));
+        let hit = raycast_aabb(Ray2D::new(Vec2::ZERO, direction), aabb).unwrap();
+        assert!(hit.point.abs_diff_eq(Vec2::new(2.0, 0.5), 1e-5));
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-6));
+
+        // Rays starting on the surface hit it right away
+        let hit = raycast_aabb(Ray2D::new(Vec2::new(2.0, 0.0), Vec2::X), aabb).unwrap();
+        assert_eq!(hit.t, 0.0);
+    }
+
+    #[test]
+    fn slanted_ray_gets_box2d_face_normal() {
+        let rotation = Vec2::from_angle(PI / 6.0);
+        let box2d = Box2D::new(Vec2::splat(-1.0), Vec2::splat(1.0), PI / 6.0);
+        let hit = raycast_box2d(Ray2D::new(Vec2::new(-5.0, 0.2), Vec2::X), box2d).unwrap();
+        assert!(hit.normal.abs_diff_eq(rotation.rotate(Vec2::NEG_X), 1e-5));
+        let local_point = Vec2::from_angle(-PI / 6.0).rotate(hit.point);
+        assert!((local_point.x + 1.0).abs() < 1e-4);
+        assert!(local_point.y.abs() <= 1.0);
+    }
+
+    #[test]
+    fn raycast_rotated_box2d() {
+        let box2d = Box2D::new(Vec2::splat(-1.0), Vec2::splat(1.0), PI / 4.0);
+        let hit = raycast_box2d(Ray2D::new(Vec2::new(-5.0, 0.0), Vec2::X), box2d).unwrap();
+        assert!((hit.t - (5.0 - 2.0_f32.sqrt())).abs() < 1e-4);
+        let expected_normal = Vec2::new(-1.0, 1.0).normalize();
+        assert!(
+            hit.normal.abs_diff_eq(expected_normal, 1e-5)
+                || hit
+                    .normal
+                    .abs_diff_eq(Vec2::new(-1.0, -1.0).normalize(), 1e-5)
+        );
+
+        let hit = raycast_box2d(Ray2D::new(Vec2::new(-5.0, 0.5), Vec2::X), box2d).unwrap();
+        assert!(hit.normal.abs_diff_eq(expected_normal, 1e-5));
+        assert!(raycast_box2d(Ray2D::new(Vec2::new(-5.0, 1.6), Vec2::X), box2d).is_none());
+    }
+
+    #[test]
+    fn cast_circle_against_circle() {
+        let c1 = Circle::new(Vec2::ZERO, 1.0);
+        let c2 = Circle::new(Vec2::new(10.0, 0.0), 2.0);
+        let hit = cast_circle_circle(c1, Vec2::new(2.0, 0.0), c2, 10.0).unwrap();
+        assert!((hit.toi - 3.5).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-6));
+        assert!(hit.point.abs_diff_eq(Vec2::new(8.0, 0.0), 1e-5));
+        assert!(cast_circle_circle(c1, Vec2::new(2.0, 0.0), c2, 3.0).is_none());
+        assert!(cast_circle_circle(c1, Vec2::new(-2.0, 0.0), c2, 10.0).is_none());
+    }
+
+    #[test]
+    fn cast_circle_against_box2d_face_and_corner() {
+        let box2d = Box2D::new(Vec2::new(4.0, -1.0), Vec2::new(6.0, 1.0), 0.0);
+        let circle = Circle::new(Vec2::new(0.0, 0.5), 1.0);
+        let hit = cast_circle_box2d(circle, Vec2::X, box2d, 10.0).unwrap();
+        assert!((hit.toi - 3.0).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-6));
+        assert!(hit.point.abs_diff_eq(Vec2::new(4.0, 0.5), 1e-5));
+
+        // Passes just above the top left corner, close enough to clip it
+        let circle = Circle::new(Vec2::new(0.0, 1.5), 1.0);
+        let hit = cast_circle_box2d(circle, Vec2::X, box2d, 10.0).unwrap();
+        assert!(hit.point.abs_diff_eq(Vec2::new(4.0, 1.0), 1e-5));
+        let expected_x = 4.0 - (1.0_f32 - 0.25).sqrt();
+        assert!((hit.toi - expected_x).abs() < 1e-4);
+
+        let circle = Circle::new(Vec2::new(0.0, 2.1), 1.0);
+        assert!(cast_circle_box2d(circle, Vec2::X, box2d, 10.0).is_none());
+
+        // The box moving towards the circle
+        let circle = Circle::new(Vec2::new(0.0, 0.5), 1.0);
+        let hit = cast_box2d_circle(box2d, Vec2::NEG_X, circle, 10.0).unwrap();
+        assert!((hit.toi - 3.0).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::X, 1e-6));
+        assert!(hit.point.abs_diff_eq(Vec2::new(1.0, 0.5), 1e-5));
+    }
+
+    #[test]
+    fn cast_box2d_against_rotated_box2d() {
+        let b1 = Box2D::new(Vec2::splat(-1.0), Vec2::splat(1.0), 0.0);
+        let b2 = Box2D::new(Vec2::new(9.0, -1.0), Vec2::new(11.0, 1.0), PI / 4.0);
+        let hit = cast_convex_convex(b1, Vec2::X, b2, 20.0).unwrap();
+        // The corner of b2 points at the face of b1
+        assert!((hit.toi - (9.0 - 2.0_f32.sqrt())).abs() < 1e-4);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-5));
+        assert!(hit
+            .point
+            .abs_diff_eq(Vec2::new(10.0 - 2.0_f32.sqrt(), 0.0), 1e-4));
+
+        assert!(cast_convex_convex(b1, Vec2::Y, b2, 20.0).is_none());
+
+        let overlapping = Box2D::new(Vec2::new(0.5, -1.0), Vec2::new(2.5, 1.0), 0.0);
+        let hit = cast_convex_convex(b1, Vec2::X, overlapping, 20.0).unwrap();
+        assert_eq!(hit.toi, 0.0);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-6));
+    }
+
+    fn triangle_at(position: Vec2) -> ConvexPolygon {
+        let mut triangle = ConvexPolygon::new(&[
+            Vec2::new(-1.0, -1.0),
+            Vec2::new(1.0, -1.0),
+            Vec2::new(0.0, 1.0),
+        ])
+        .unwrap();
+        triangle.position = position;
+        triangle
+    }
+
+    #[test]
+    fn polygon_point_and_raycast() {
+        let triangle = triangle_at(Vec2::ZERO);
+        assert!(is_point_in_polygon(Vec2::new(0.0, 0.5), &triangle));
+        assert!(!is_point_in_polygon(Vec2::new(0.8, 0.5), &triangle));
+
+        let hit = raycast_polygon(Ray2D::new(Vec2::new(0.0, -5.0), Vec2::Y), &triangle).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_Y, 1e-6));
+        let hit = raycast_polygon(Ray2D::new(Vec2::ZERO, Vec2::NEG_Y), &triangle).unwrap();
+        assert!((hit.t - 1.0).abs() < 1e-5);
+        assert!(raycast_polygon(Ray2D::new(Vec2::new(2.0, -5.0), Vec2::Y), &triangle).is_none());
+    }
+
+    #[test]
+    fn polygon_polygon_face_contact() {
+        // Tip of the second triangle pokes into the bottom face of the first
+        let p1 = triangle_at(Vec2::ZERO);
+        let mut p2 = triangle_at(Vec2::new(0.0, -1.9));
+        let manifold = polygon_polygon(&p1, &p2).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::NEG_Y, 1e-5));
+        assert!((manifold.depth - 0.1).abs() < 1e-4);
+        assert!(manifold
+            .contact_point_b
+            .abs_diff_eq(Vec2::new(0.0, -0.9), 1e-4));
+        let flipped = polygon_polygon(&p2, &p1).unwrap();
+        assert!(flipped.normal.abs_diff_eq(Vec2::Y, 1e-5));
+
+        p2.position.y = -2.1;
+        assert!(polygon_polygon(&p1, &p2).is_none());
+
+        // Flat face on flat face, the contact is in the middle of the overlap
+        let b1: ConvexPolygon = Box2D::new(Vec2::ZERO, Vec2::splat(2.0), 0.0).into();
+        let b2: ConvexPolygon = Box2D::new(Vec2::new(1.0, 1.9), Vec2::new(5.0, 3.0), 0.0).into();
+        let manifold = polygon_polygon(&b1, &b2).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-5));
+        assert!((manifold.depth - 0.1).abs() < 1e-4);
+        assert!((manifold.contact_point_a.x - 1.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn polygon_circle_normal_points_towards_circle() {
+        let triangle = triangle_at(Vec2::ZERO);
+        let manifold = polygon_circle(&triangle, Circle::new(Vec2::new(0.0, -1.5), 1.0)).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::NEG_Y, 1e-5));
+        assert!((manifold.depth - 0.5).abs() < 1e-5);
+
+        // Closest to a vertex
+        let center = Vec2::new(1.5, -1.5);
+        let manifold = polygon_circle(&triangle, Circle::new(center, 1.0)).unwrap();
+        let expected = (center - Vec2::new(1.0, -1.0)).normalize();
+        assert!(manifold.normal.abs_diff_eq(expected, 1e-5));
+        assert!(manifold
+            .contact_point_a
+            .abs_diff_eq(Vec2::new(1.0, -1.0), 1e-5));
+
+        assert!(polygon_circle(&triangle, Circle::new(Vec2::new(2.0, -2.0), 1.0)).is_none());
+    }
+
+    #[test]
+    fn capsule_collides_with_circle_and_capsule() {
+        let mut capsule = Capsule::vertical(1.0, 0.5);
+        capsule.rotation = std::f32::consts::FRAC_PI_2;
+        let manifold = capsule_circle(capsule, Circle::new(Vec2::new(0.8, 0.9), 0.5)).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-5));
+        assert!((manifold.depth - 0.1).abs() < 1e-5);
+        assert!(capsule_circle(capsule, Circle::new(Vec2::new(1.8, 0.0), 0.25)).is_none());
+
+        let mut other = Capsule::vertical(1.0, 0.5);
+        other.position = Vec2::new(0.5, 1.8);
+        let manifold = capsule_capsule(capsule, other).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-5));
+        assert!((manifold.depth - 0.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn polygon_capsule_pushes_capsule_out() {
+        let ground: ConvexPolygon = Box2D::new(Vec2::new(-5.0, -1.0), Vec2::ZERO, 0.0).into();
+        let mut capsule = Capsule::vertical(1.0, 0.5);
+        capsule.position = Vec2::new(-1.0, 1.4);
+        let manifold = polygon_capsule(&ground, capsule).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-5));
+        assert!((manifold.depth - 0.1).abs() < 1e-5);
+        assert!(manifold
+            .contact_point_a
+            .abs_diff_eq(Vec2::new(-1.0, 0.0), 1e-5));
+
+        // Lying down with the segment inside the ground
+        capsule.rotation = std::f32::consts::FRAC_PI_2;
+        capsule.position = Vec2::new(-2.0, -0.2);
+        let manifold = polygon_capsule(&ground, capsule).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-5));
+        assert!((manifold.depth - 0.7).abs() < 1e-5);
+
+        // Next to the corner
+        capsule.position = Vec2::new(1.3, 0.3);
+        let manifold = polygon_capsule(&ground, capsule).unwrap();
+        assert!(manifold
+            .normal
+            .abs_diff_eq(Vec2::new(1.0, 1.0).normalize(), 1e-5));
+
+        capsule.position = Vec2::new(3.0, 0.0);
+        assert!(polygon_capsule(&ground, capsule).is_none());
+    }
+
+    #[test]
+    fn polygon_capsule_finds_core_crossing_polygon() {
+        let ground: ConvexPolygon = Box2D::new(Vec2::new(-5.0, -1.0), Vec2::ZERO, 0.0).into();
+        // Both ends of the core are outside the ground. Its distance to the
+        // ground is zero where it crosses the top, which used to pass for
+        // barely touching.
+        for radius in [0.0, 0.1] {
+            let capsule = Capsule::new(Vec2::new(-0.9, -1.2), Vec2::new(-2.5, 1.1), radius);
+            let manifold = polygon_capsule(&ground, capsule).unwrap();
+            assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-5));
+            assert!((manifold.depth - (1.2 + radius)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn raycast_capsule_from_outside_and_inside() {
+        let capsule = Capsule::vertical(1.0, 0.5);
+        let hit = raycast_capsule(Ray2D::new(Vec2::new(-3.0, 0.5), Vec2::X), capsule).unwrap();
+        assert!((hit.t - 2.5).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-5));
+        let hit = raycast_capsule(Ray2D::new(Vec2::new(0.0, -5.0), Vec2::Y), capsule).unwrap();
+        assert!((hit.t - 3.5).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_Y, 1e-5));
+
+        let hit = raycast_capsule(Ray2D::new(Vec2::ZERO, Vec2::Y), capsule).unwrap();
+        assert!((hit.t - 1.5).abs() < 1e-4);
+        assert!(hit.normal.abs_diff_eq(Vec2::Y, 1e-4));
+        assert!(raycast_capsule(Ray2D::new(Vec2::new(-3.0, 0.0), Vec2::Y), capsule).is_none());
+    }
+
+    #[test]
+    fn raycast_segment_from_both_sides() {
+        let segment = Line2D::new(Vec2::ZERO, Vec2::new(2.0, 0.0));
+        let hit = raycast_segment(Ray2D::new(Vec2::new(1.0, -5.0), Vec2::Y), segment).unwrap();
+        assert!((hit.t - 5.0).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_Y, 1e-6));
+        let hit = raycast_segment(Ray2D::new(Vec2::new(1.0, 5.0), Vec2::NEG_Y), segment).unwrap();
+        assert!(hit.normal.abs_diff_eq(Vec2::Y, 1e-6));
+        assert!(raycast_segment(Ray2D::new(Vec2::new(3.0, -5.0), Vec2::Y), segment).is_none());
+        assert!(raycast_segment(Ray2D::new(Vec2::new(1.0, -5.0), Vec2::NEG_Y), segment).is_none());
+    }
+
+    #[test]
+    fn polyline_ghost_vertices_prevent_catching() {
+        // Flat ground facing up, split in two
+        let ground = Polyline::new(vec![
+            Vec2::new(4.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(0.0, 0.0),
+        ])
+        .unwrap();
+        // Just past the inner vertex the box corner is closest to the side of
+        // the second segment
+        let b = ColliderShape::Box2D(Box2D::new(Vec2::new(1.95, -0.1), Vec2::new(2.95, 0.9), 0.0));
+        let lone_segment = ColliderShape::Segment(Segment::new(Vec2::new(2.0, 0.0), Vec2::ZERO));
+        let manifold = lone_segment.test_collision(&b).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::X, 1e-5));
+
+        let manifold = polyline_shape(&ground, &b).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-5));
+        assert!((manifold.depth - 0.1).abs() < 1e-5);
+
+        // Only the front side collides
+        let below = ColliderShape::Circle(Circle::new(Vec2::new(1.0, -0.3), 0.5));
+        assert!(polyline_shape(&ground, &below).is_none());
+        assert!(lone_segment.test_collision(&below).is_some());
+    }
+
+    #[test]
+    fn polyline_convex_corner_keeps_vertex_normal() {
+        let hill = Polyline::new(vec![
+            Vec2::new(4.0, 0.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(0.0, 0.0),
+        ])
+        .unwrap();
+        let circle = ColliderShape::Circle(Circle::new(Vec2::new(2.0, 1.4), 0.5));
+        let manifold = polyline_shape(&hill, &circle).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-5));
+        assert!((manifold.depth - 0.1).abs() < 1e-5);
+    }
+
+    fn bump_at_two() -> Heightfield {
+        let mut heightfield =
+            Heightfield::new(vec![0.0, 0.0, 1.0, 0.0], Vec2::new(1.0, -1.0)).unwrap();
+        heightfield.position = Vec2::new(0.0, 10.0);
+        heightfield
+    }
+
+    #[test]
+    fn heightfield_pushes_shapes_up() {
+        let heightfield = bump_at_two();
+        let circle = ColliderShape::Circle(Circle::new(Vec2::new(0.5, 9.6), 0.5));
+        let manifold = heightfield_shape(&heightfield, &circle).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::NEG_Y, 1e-5));
+        assert!((manifold.depth - 0.1).abs() < 1e-5);
+
+        let b = ColliderShape::Box2D(Box2D::new(Vec2::new(0.2, 9.0), Vec2::new(0.8, 10.05), 0.0));
+        let manifold = heightfield_shape(&heightfield, &b).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::NEG_Y, 1e-5));
+        assert!((manifold.depth - 0.05).abs() < 1e-5);
+
+        let mut diamond = ConvexPolygon::regular(4, 0.5).unwrap();
+        diamond.position = Vec2::new(0.5, 9.7);
+        let manifold = heightfield_shape(&heightfield, &ColliderShape::Polygon(diamond)).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::NEG_Y, 1e-5));
+        assert!((manifold.depth - 0.2).abs() < 1e-5);
+
+        let past_the_end = ColliderShape::Circle(Circle::new(Vec2::new(4.0, 10.0), 0.5));
+        assert!(heightfield_shape(&heightfield, &past_the_end).is_none());
+    }
+
+    #[test]
+    fn raycast_heightfield_walks_cells_along_ray() {
+        let heightfield = bump_at_two();
+        let hit = raycast_heightfield(Ray2D::new(Vec2::new(-1.0, 9.5), Vec2::X), &heightfield);
+        let hit = hit.unwrap();
+        assert!((hit.t - 2.5).abs() < 1e-5);
+        assert!(hit
+            .normal
+            .abs_diff_eq(Vec2::new(-1.0, -1.0).normalize(), 1e-5));
+
+        let hit = raycast_heightfield(Ray2D::new(Vec2::new(5.0, 9.5), Vec2::NEG_X), &heightfield);
+        assert!((hit.unwrap().t - 2.5).abs() < 1e-5);
+
+        let hit = raycast_heightfield(Ray2D::new(Vec2::new(2.5, 0.0), Vec2::Y), &heightfield);
+        let hit = hit.unwrap();
+        assert!((hit.t - 9.5).abs() < 1e-5);
+        assert!(hit.normal.y < 0.0);
+
+        let down_past_the_end = Ray2D::new(Vec2::new(3.5, 0.0), Vec2::Y);
+        assert!(raycast_heightfield(down_past_the_end, &heightfield).is_none());
+    }
+
+    fn u_shaped_mesh() -> TriMesh {
+        let outline = [
+            Vec2::ZERO,
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 2.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 2.0),
+            Vec2::new(0.0, 2.0),
+        ];
+        TriMesh::new(&outline, &crate::triangulate(&outline).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn trimesh_collides_inside_its_hollow() {
+        let mesh = u_shaped_mesh();
+        assert!(is_point_in_trimesh(Vec2::new(0.5, 1.5), &mesh));
+        assert!(!is_point_in_trimesh(Vec2::new(1.5, 1.5), &mesh));
+
+        // Resting in the middle of the U
+        let circle = ColliderShape::Circle(Circle::new(Vec2::new(1.5, 1.4), 0.5));
+        let manifold = trimesh_shape(&mesh, &circle).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-5));
+        assert!((manifold.depth - 0.1).abs() < 1e-5);
+        let small = ColliderShape::Circle(Circle::new(Vec2::new(1.5, 1.5), 0.25));
+        assert!(trimesh_shape(&mesh, &small).is_none());
+    }
+
+    #[test]
+    fn raycast_trimesh_skips_internal_edges() {
+        let mesh = u_shaped_mesh();
+        // Starting inside the bottom of the U, the ray crosses several
+        // triangles before it leaves through the right side
+        let hit = raycast_trimesh(Ray2D::new(Vec2::new(0.5, 0.5), Vec2::X), &mesh).unwrap();
+        assert!((hit.t - 2.5).abs() < 1e-4);
+        assert!(hit.normal.abs_diff_eq(Vec2::X, 1e-4));
+
+        let hit = raycast_trimesh(Ray2D::new(Vec2::new(0.5, 1.5), Vec2::NEG_Y), &mesh).unwrap();
+        assert!((hit.t - 1.5).abs() < 1e-4);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_Y, 1e-4));
+    }
+
+    #[test]
+    fn raycast_rotated_trimesh() {
+        let mut mesh = u_shaped_mesh();
+        mesh.position = Vec2::new(10.0, 0.0);
+        mesh.rotation = PI;
+        // The U now opens downwards, from x = 7 to 10
+        let hit = raycast_trimesh(Ray2D::new(Vec2::new(8.5, -5.0), Vec2::Y), &mesh).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-4);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_Y, 1e-4));
+        let hit = raycast_trimesh(Ray2D::new(Vec2::new(5.0, -1.5), Vec2::X), &mesh).unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-4);
+        assert!(raycast_trimesh(Ray2D::new(Vec2::new(5.0, 1.0), Vec2::X), &mesh).is_none());
+    }
 }
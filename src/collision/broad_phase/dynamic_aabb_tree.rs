@@ -0,0 +1,625 @@
+use std::collections::HashMap;
+
+use glam::Vec2;
+
+use crate::{
+    collision::{ColliderHandle, ColliderSet},
+    Aabb, Ray2D,
+};
+
+use super::BroadPhase;
+
+const NULL_NODE: usize = usize::MAX;
+
+// The generation tells a proxy apart from later ones that reuse its node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProxyId {
+    index: usize,
+    generation: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    // Fattened bounds for leaves, union of the children for internal nodes
+    aabb: Aabb,
+    parent: usize,
+    child1: usize,
+    child2: usize,
+    // Leaves have a height of 0
+    height: i32,
+    // Bumped every time the node is freed
+    generation: u32,
+    data: Option<T>,
+}
+
+impl<T> Node<T> {
+    fn is_leaf(&self) -> bool {
+        self.child1 == NULL_NODE
+    }
+}
+
+// Bounding volume hierarchy over fattened AABBs, kept balanced with tree
+// rotations. Leaves only have to be reinserted when their object moves out
+// of the fattened bounds.
+#[derive(Debug, Clone)]
+pub struct DynamicAabbTree<T> {
+    nodes: Vec<Node<T>>,
+    free_nodes: Vec<usize>,
+    root: usize,
+    margin: f32,
+}
+
+impl<T: Copy> DynamicAabbTree<T> {
+    pub const DEFAULT_MARGIN: f32 = 0.1;
+
+    pub fn new() -> Self {
+        Self::with_margin(Self::DEFAULT_MARGIN)
+    }
+
+    pub fn with_margin(margin: f32) -> Self {
+        Self {
+            nodes: Vec::new(),
+            free_nodes: Vec::new(),
+            root: NULL_NODE,
+            margin,
+        }
+    }
+
+    pub fn insert(&mut self, aabb: Aabb, data: T) -> ProxyId {
+        let leaf = self.allocate_node(aabb.loosened(self.margin), Some(data));
+        self.insert_leaf(leaf);
+        self.proxy_id(leaf)
+    }
+
+    pub fn remove(&mut self, proxy: ProxyId) -> Option<T> {
+        let data = self.leaf(proxy)?.data?;
+        self.remove_leaf(proxy.index);
+        self.free_node(proxy.index);
+        Some(data)
+    }
+
+    // Returns true if the proxy had to be reinserted. Proxies that were
+    // removed are left alone.
+    pub fn move_proxy(&mut self, proxy: ProxyId, aabb: Aabb) -> bool {
+        let Some(leaf) = self.leaf(proxy) else {
+            return false;
+        };
+        let fat_aabb = leaf.aabb;
+        let leaf = proxy.index;
+        // Also reinsert when the object shrank a lot, so the fat bounds
+        // don't grow stale
+        let big_aabb = aabb.loosened(self.margin * 4.0);
+        if fat_aabb.contains(aabb) && big_aabb.contains(fat_aabb) {
+            return false;
+        }
+
+        self.remove_leaf(leaf);
+        self.nodes[leaf].aabb = aabb.loosened(self.margin);
+        self.insert_leaf(leaf);
+        true
+    }
+
+    pub fn fat_aabb(&self, proxy: ProxyId) -> Option<Aabb> {
+        Some(self.leaf(proxy)?.aabb)
+    }
+
+    pub fn data(&self, proxy: ProxyId) -> Option<T> {
+        self.leaf(proxy)?.data
+    }
+
+    // The node of a proxy that is still in the tree
+    fn leaf(&self, proxy: ProxyId) -> Option<&Node<T>> {
+        self.nodes
+            .get(proxy.index)
+            .filter(|node| node.generation == proxy.generation && node.data.is_some())
+    }
+
+    fn proxy_id(&self, index: usize) -> ProxyId {
+        ProxyId {
+            index,
+            generation: self.nodes[index].generation,
+        }
+    }
+
+    pub fn height(&self) -> i32 {
+        if self.root == NULL_NODE {
+            0
+        } else {
+            self.nodes[self.root].height
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root == NULL_NODE
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free_nodes.clear();
+        self.root = NULL_NODE;
+    }
+
+    // Calls `callback` for every leaf whose fat bounds overlap `aabb`,
+    // until it returns false
+    pub fn query_aabb(&self, aabb: Aabb, callback: impl FnMut(ProxyId, T) -> bool) {
+        self.traverse(|node| node.aabb.intersects(aabb), callback);
+    }
+
+    pub fn query_point(&self, point: Vec2, callback: impl FnMut(ProxyId, T) -> bool) {
+        self.traverse(|node| node.aabb.contains_point(point), callback);
+    }
+
+    // Calls `callback` for every leaf whose fat bounds are hit by the ray
+    // within `max_t`. The callback returns the new maximum distance, so
+    // returning the distance of a hit only keeps looking for closer ones,
+    // and returning a negative value stops the traversal. Returning 0 still
+    // visits the leaves that contain the origin of the ray.
+    pub fn cast_ray(&self, ray: Ray2D, max_t: f32, mut callback: impl FnMut(ProxyId, T) -> f32) {
+        if self.root == NULL_NODE {
+            return;
+        }
+
+        let mut max_t = max_t;
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.aabb.cast_ray(ray, max_t).is_none() {
+                continue;
+            }
+
+            if let Some(data) = node.data {
+                max_t = max_t.min(callback(self.proxy_id(index), data));
+                if max_t < 0.0 {
+                    return;
+                }
+            } else {
+                stack.push(node.child1);
+                stack.push(node.child2);
+            }
+        }
+    }
+
+    fn traverse(
+        &self,
+        mut overlaps: impl FnMut(&Node<T>) -> bool,
+        mut callback: impl FnMut(ProxyId, T) -> bool,
+    ) {
+        if self.root == NULL_NODE {
+            return;
+        }
+
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !overlaps(node) {
+                continue;
+            }
+
+            if let Some(data) = node.data {
+                if !callback(self.proxy_id(index), data) {
+                    return;
+                }
+            } else {
+                stack.push(node.child1);
+                stack.push(node.child2);
+            }
+        }
+    }
+
+    fn allocate_node(&mut self, aabb: Aabb, data: Option<T>) -> usize {
+        let node = Node {
+            aabb,
+            parent: NULL_NODE,
+            child1: NULL_NODE,
+            child2: NULL_NODE,
+            height: 0,
+            generation: 0,
+            data,
+        };
+        if let Some(index) = self.free_nodes.pop() {
+            let generation = self.nodes[index].generation;
+            self.nodes[index] = Node { generation, ..node };
+            index
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+
+    fn free_node(&mut self, index: usize) {
+        self.nodes[index].data = None;
+        self.nodes[index].height = -1;
+        self.nodes[index].generation = self.nodes[index].generation.wrapping_add(1);
+        self.free_nodes.push(index);
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        if self.root == NULL_NODE {
+            self.root = leaf;
+            self.nodes[leaf].parent = NULL_NODE;
+            return;
+        }
+
+        // Find the best sibling using the surface area heuristic
+        let leaf_aabb = self.nodes[leaf].aabb;
+        let mut index = self.root;
+        while !self.nodes[index].is_leaf() {
+            let node = &self.nodes[index];
+            let (child1, child2) = (node.child1, node.child2);
+            let area = node.aabb.perimeter();
+            let combined_area = node.aabb.merged(leaf_aabb).perimeter();
+
+            // Cost of creating a new parent for this node and the new leaf
+            let cost = 2.0 * combined_area;
+            // Minimum cost of pushing the leaf further down the tree
+            let inheritance_cost = 2.0 * (combined_area - area);
+            let descend_cost = |child: usize| {
+                let child = &self.nodes[child];
+                let merged_area = child.aabb.merged(leaf_aabb).perimeter();
+                if child.is_leaf() {
+                    merged_area + inheritance_cost
+                } else {
+                    merged_area - child.aabb.perimeter() + inheritance_cost
+                }
+            };
+            let cost1 = descend_cost(child1);
+            let cost2 = descend_cost(child2);
+
+            if cost < cost1 && cost < cost2 {
+                break;
+            }
+
+            index = if cost1 < cost2 { child1 } else { child2 };
+        }
+
+        let sibling = index;
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate_node(self.nodes[sibling].aabb.merged(leaf_aabb), None);
+        self.nodes[new_parent].parent = old_parent;
+        self.nodes[new_parent].height = self.nodes[sibling].height + 1;
+        self.nodes[new_parent].child1 = sibling;
+        self.nodes[new_parent].child2 = leaf;
+        self.nodes[sibling].parent = new_parent;
+        self.nodes[leaf].parent = new_parent;
+
+        if old_parent == NULL_NODE {
+            self.root = new_parent;
+        } else {
+            self.replace_child(old_parent, sibling, new_parent);
+        }
+
+        self.refit_ancestors(new_parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if leaf == self.root {
+            self.root = NULL_NODE;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent;
+        let grand_parent = self.nodes[parent].parent;
+        let sibling = if self.nodes[parent].child1 == leaf {
+            self.nodes[parent].child2
+        } else {
+            self.nodes[parent].child1
+        };
+
+        self.nodes[sibling].parent = grand_parent;
+        self.free_node(parent);
+        if grand_parent == NULL_NODE {
+            self.root = sibling;
+        } else {
+            self.replace_child(grand_parent, parent, sibling);
+            self.refit_ancestors(grand_parent);
+        }
+    }
+
+    fn replace_child(&mut self, parent: usize, old_child: usize, new_child: usize) {
+        let parent = &mut self.nodes[parent];
+        if parent.child1 == old_child {
+            parent.child1 = new_child;
+        } else {
+            parent.child2 = new_child;
+        }
+    }
+
+    // Walks from `index` up to the root, rebalancing and fixing the bounds
+    // and heights on the way
+    fn refit_ancestors(&mut self, mut index: usize) {
+        while index != NULL_NODE {
+            index = self.balance(index);
+            self.refit(index);
+            index = self.nodes[index].parent;
+        }
+    }
+
+    fn refit(&mut self, index: usize) {
+        let (child1, child2) = (self.nodes[index].child1, self.nodes[index].child2);
+        let (child1, child2) = (&self.nodes[child1], &self.nodes[child2]);
+        let height = 1 + child1.height.max(child2.height);
+        let aabb = child1.aabb.merged(child2.aabb);
+        self.nodes[index].height = height;
+        self.nodes[index].aabb = aabb;
+    }
+
+    // If one child of `a` is more than one level taller than the other,
+    // rotate the taller child up. Returns the index of the new subtree root.
+    fn balance(&mut self, a: usize) -> usize {
+        if self.nodes[a].is_leaf() || self.nodes[a].height < 2 {
+            return a;
+        }
+
+        let b = self.nodes[a].child1;
+        let c = self.nodes[a].child2;
+        let balance = self.nodes[c].height - self.nodes[b].height;
+        if balance > 1 {
+            self.rotate_up(a, c, b)
+        } else if balance < -1 {
+            self.rotate_up(a, b, c)
+        } else {
+            a
+        }
+    }
+
+    // Makes `child` the parent of `a`. `other` is the remaining child of `a`.
+    fn rotate_up(&mut self, a: usize, child: usize, other: usize) -> usize {
+        let f = self.nodes[child].child1;
+        let g = self.nodes[child].child2;
+
+        let a_parent = self.nodes[a].parent;
+        self.nodes[child].parent = a_parent;
+        self.nodes[a].parent = child;
+        if a_parent == NULL_NODE {
+            self.root = child;
+        } else {
+            self.replace_child(a_parent, a, child);
+        }
+
+        // The taller grandchild stays under `child`, the other one moves to `a`
+        let (keep, moved) = if self.nodes[f].height > self.nodes[g].height {
+            (f, g)
+        } else {
+            (g, f)
+        };
+        self.nodes[child].child1 = a;
+        self.nodes[child].child2 = keep;
+        self.replace_child(a, child, moved);
+        self.nodes[moved].parent = a;
+        debug_assert!(
+            self.nodes[a].child1 == other || self.nodes[a].child2 == other,
+            "rotated node lost its other child"
+        );
+
+        self.refit(a);
+        self.refit(child);
+        child
+    }
+
+    #[cfg(test)]
+    fn validate(&self) {
+        if self.root == NULL_NODE {
+            return;
+        }
+
+        assert_eq!(self.nodes[self.root].parent, NULL_NODE);
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.is_leaf() {
+                assert_eq!(node.height, 0);
+                assert!(node.data.is_some());
+                continue;
+            }
+
+            let (child1, child2) = (&self.nodes[node.child1], &self.nodes[node.child2]);
+            assert_eq!(child1.parent, index);
+            assert_eq!(child2.parent, index);
+            assert_eq!(node.height, 1 + child1.height.max(child2.height));
+            assert!((child1.height - child2.height).abs() <= 1);
+            assert!(node.aabb.contains(child1.aabb));
+            assert!(node.aabb.contains(child2.aabb));
+            stack.push(node.child1);
+            stack.push(node.child2);
+        }
+    }
+}
+
+impl<T: Copy> Default for DynamicAabbTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Broadphase backed by a `DynamicAabbTree` over every collider
+#[derive(Debug, Clone, Default)]
+pub struct DynamicTreeBroadPhase {
+    tree: DynamicAabbTree<ColliderHandle>,
+    proxies: HashMap<ColliderHandle, ProxyId>,
+}
+
+impl DynamicTreeBroadPhase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tree(&self) -> &DynamicAabbTree<ColliderHandle> {
+        &self.tree
+    }
+}
+
+impl BroadPhase for DynamicTreeBroadPhase {
+    fn update(&mut self, colliders: &ColliderSet) {
+        let tree = &mut self.tree;
+        self.proxies.retain(|handle, proxy| {
+            let is_alive = colliders.get(*handle).is_some();
+            if !is_alive {
+                tree.remove(*proxy);
+            }
+            is_alive
+        });
+
+        for (handle, collider) in colliders.iter() {
+            let aabb = collider.shape.compute_aabb();
+            // Non-finite bounds would poison the costs of the tree, so those
+            // colliders are left out until they are finite again
+            if !aabb.min.is_finite() || !aabb.max.is_finite() {
+                if let Some(proxy) = self.proxies.remove(&handle) {
+                    self.tree.remove(proxy);
+                }
+                continue;
+            }
+            match self.proxies.get(&handle) {
+                Some(proxy) => {
+                    self.tree.move_proxy(*proxy, aabb);
+                }
+                None => {
+                    let proxy = self.tree.insert(aabb, handle);
+                    self.proxies.insert(handle, proxy);
+                }
+            }
+        }
+    }
+
+    fn find_pairs(&self, pairs: &mut Vec<(ColliderHandle, ColliderHandle)>) {
+        for (handle, proxy) in self.proxies.iter() {
+            let Some(fat_aabb) = self.tree.fat_aabb(*proxy) else {
+                continue;
+            };
+            self.tree.query_aabb(fat_aabb, |_, other_handle| {
+                // Each pair is found from both sides, only keep one of them
+                if *handle < other_handle {
+                    pairs.push((*handle, other_handle));
+                }
+                true
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::test_utils::circle_collider, *};
+
+    // Small deterministic generator so the tests don't need a rand dependency
+    struct Lcg(u32);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self.0.wrapping_mul(1664525).wrapping_add(1013904223);
+            (self.0 >> 8) as f32 / (1 << 24) as f32
+        }
+
+        fn aabb(&mut self) -> Aabb {
+            let min = Vec2::new(self.next(), self.next()) * 100.0;
+            let size = Vec2::new(self.next(), self.next()) * 5.0;
+            Aabb::new(min, min + size)
+        }
+    }
+
+    fn query(tree: &DynamicAabbTree<usize>, aabb: Aabb) -> Vec<usize> {
+        let mut found = Vec::new();
+        tree.query_aabb(aabb, |_, data| {
+            found.push(data);
+            true
+        });
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn stays_balanced_and_finds_overlaps() {
+        let mut rng = Lcg(7);
+        let mut tree = DynamicAabbTree::with_margin(0.0);
+        let mut aabbs = Vec::new();
+        let mut proxies = Vec::new();
+        for i in 0..200 {
+            let aabb = rng.aabb();
+            aabbs.push(aabb);
+            proxies.push(tree.insert(aabb, i));
+        }
+        tree.validate();
+        assert!(tree.height() <= 16);
+
+        // Remove every other leaf and move the rest
+        for i in (0..200).step_by(2) {
+            assert_eq!(tree.remove(proxies[i]), Some(i));
+        }
+        for i in (1..200).step_by(2) {
+            aabbs[i] = rng.aabb();
+            tree.move_proxy(proxies[i], aabbs[i]);
+        }
+        tree.validate();
+
+        for _ in 0..20 {
+            let region = rng.aabb().loosened(10.0);
+            let expected: Vec<_> = (1..200)
+                .step_by(2)
+                .filter(|i| aabbs[*i].intersects(region))
+                .collect();
+            assert_eq!(query(&tree, region), expected);
+        }
+    }
+
+    #[test]
+    fn stale_proxies_dont_touch_reused_nodes() {
+        let mut tree = DynamicAabbTree::with_margin(0.0);
+        let aabb = Aabb::new(Vec2::ZERO, Vec2::ONE);
+        let stale = tree.insert(aabb, 0);
+        tree.remove(stale);
+        let proxy = tree.insert(aabb, 1);
+
+        assert_eq!(tree.data(stale), None);
+        assert_eq!(tree.fat_aabb(stale), None);
+        assert_eq!(tree.remove(stale), None);
+        assert!(!tree.move_proxy(stale, Aabb::new(Vec2::splat(10.0), Vec2::splat(11.0))));
+        assert_eq!(tree.data(proxy), Some(1));
+        assert_eq!(tree.fat_aabb(proxy), Some(aabb));
+        tree.validate();
+    }
+
+    #[test]
+    fn broad_phase_keeps_non_finite_bounds_out_of_the_tree() {
+        let mut colliders = ColliderSet::new();
+        colliders.insert(circle_collider(f32::NAN, 0.0));
+        colliders.insert(circle_collider(f32::INFINITY, 0.0));
+        for i in 0..50 {
+            colliders.insert(circle_collider(i as f32 * 3.0, 0.0));
+        }
+        let mut broad_phase = DynamicTreeBroadPhase::new();
+        broad_phase.update(&colliders);
+        broad_phase.tree().validate();
+        assert!(broad_phase.tree().height() <= 8);
+    }
+
+    #[test]
+    fn ray_visits_hit_leaves() {
+        let mut tree = DynamicAabbTree::with_margin(0.0);
+        tree.insert(Aabb::new(Vec2::new(5.0, -1.0), Vec2::new(6.0, 1.0)), 0);
+        tree.insert(Aabb::new(Vec2::new(10.0, -1.0), Vec2::new(11.0, 1.0)), 1);
+        tree.insert(Aabb::new(Vec2::new(5.0, 5.0), Vec2::new(6.0, 6.0)), 2);
+
+        let ray = Ray2D::new(Vec2::ZERO, Vec2::X);
+        let mut hits = Vec::new();
+        tree.cast_ray(ray, f32::MAX, |_, data| {
+            hits.push(data);
+            f32::MAX
+        });
+        hits.sort();
+        assert_eq!(hits, vec![0, 1]);
+
+        let mut hits = Vec::new();
+        tree.cast_ray(ray, 8.0, |_, data| {
+            hits.push(data);
+            f32::MAX
+        });
+        assert_eq!(hits, vec![0]);
+
+        let mut visited = 0;
+        tree.cast_ray(ray, f32::MAX, |_, _| {
+            visited += 1;
+            -1.0
+        });
+        assert_eq!(visited, 1);
+    }
+}
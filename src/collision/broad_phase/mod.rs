@@ -0,0 +1,130 @@
+mod dynamic_aabb_tree;
+mod spatial_hash;
+mod sweep_and_prune;
+
+pub use dynamic_aabb_tree::*;
+pub use spatial_hash::*;
+pub use sweep_and_prune::*;
+
+use super::{ColliderHandle, ColliderSet};
+
+// Finds the pairs of colliders whose bounds overlap, so the narrowphase only
+// has to test those
+pub trait BroadPhase {
+    fn update(&mut self, colliders: &ColliderSet);
+
+    // Pushes each overlapping pair once, as (smaller handle, larger handle)
+    fn find_pairs(&self, pairs: &mut Vec<(ColliderHandle, ColliderHandle)>);
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use glam::Vec2;
+
+    use super::BroadPhase;
+    use crate::{
+        collision::{Collider, ColliderHandle, ColliderShape},
+        Circle,
+    };
+
+    pub(crate) fn circle_collider(x: f32, y: f32) -> Collider {
+        Collider::new(ColliderShape::Circle(Circle::new(Vec2::new(x, y), 1.0)))
+    }
+
+    pub(crate) fn sorted_pairs(
+        broad_phase: &dyn BroadPhase,
+    ) -> Vec<(ColliderHandle, ColliderHandle)> {
+        let mut pairs = Vec::new();
+        broad_phase.find_pairs(&mut pairs);
+        pairs.sort();
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::{test_utils::*, *};
+    use crate::RigidBodySet;
+
+    // Every implementation has to pass the same tests
+    fn broad_phases() -> Vec<(&'static str, Box<dyn BroadPhase>)> {
+        vec![
+            ("sweep and prune", Box::new(SweepAndPrune::new())),
+            ("dynamic tree", Box::new(DynamicTreeBroadPhase::new())),
+            ("spatial hash", Box::new(SpatialHash::new(2.0))),
+        ]
+    }
+
+    #[test]
+    fn finds_only_overlapping_pairs() {
+        let mut colliders = ColliderSet::new();
+        let a = colliders.insert(circle_collider(0.0, 0.0));
+        let b = colliders.insert(circle_collider(1.5, 0.0));
+        colliders.insert(circle_collider(1.5, 10.0));
+        colliders.insert(circle_collider(10.0, 0.0));
+
+        for (name, mut broad_phase) in broad_phases() {
+            broad_phase.update(&colliders);
+            assert_eq!(
+                sorted_pairs(broad_phase.as_ref()),
+                vec![(a.min(b), a.max(b))],
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn skips_colliders_with_non_finite_bounds() {
+        for (name, mut broad_phase) in broad_phases() {
+            let mut colliders = ColliderSet::new();
+            let a = colliders.insert(circle_collider(0.0, 0.0));
+            let b = colliders.insert(circle_collider(1.5, 0.0));
+            let c = colliders.insert(circle_collider(0.5, 0.0));
+            colliders.insert(circle_collider(f32::NAN, 0.0));
+            colliders.insert(circle_collider(f32::INFINITY, 0.0));
+            broad_phase.update(&colliders);
+
+            // A collider that already had finite bounds drops out too
+            colliders[c].update_position(Vec2::new(f32::NAN, f32::NAN));
+            broad_phase.update(&colliders);
+            assert_eq!(
+                sorted_pairs(broad_phase.as_ref()),
+                vec![(a.min(b), a.max(b))],
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn follows_moved_and_removed_colliders() {
+        for (name, mut broad_phase) in broad_phases() {
+            let mut colliders = ColliderSet::new();
+            let mut bodies = RigidBodySet::new();
+            let a = colliders.insert(circle_collider(0.0, 0.0));
+            let b = colliders.insert(circle_collider(5.0, 0.0));
+            let c = colliders.insert(circle_collider(20.0, 0.0));
+
+            broad_phase.update(&colliders);
+            assert!(sorted_pairs(broad_phase.as_ref()).is_empty(), "{name}");
+
+            colliders[c].update_position(Vec2::new(-1.0, 0.0));
+            broad_phase.update(&colliders);
+            assert_eq!(
+                sorted_pairs(broad_phase.as_ref()),
+                vec![(a.min(c), a.max(c))],
+                "{name}"
+            );
+
+            colliders.remove(a, &mut bodies);
+            colliders[b].update_position(Vec2::new(0.0, 0.0));
+            broad_phase.update(&colliders);
+            assert_eq!(
+                sorted_pairs(broad_phase.as_ref()),
+                vec![(b.min(c), b.max(c))],
+                "{name}"
+            );
+        }
+    }
+}
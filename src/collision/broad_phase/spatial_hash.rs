@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use glam::Vec2;
+
+use crate::{
+    collision::{ColliderHandle, ColliderSet},
+    Aabb,
+};
+
+use super::BroadPhase;
+
+type Cell = (i32, i32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Entry {
+    handle: ColliderHandle,
+    aabb: Aabb,
+    // Set for the entries in `SpatialHash::oversized`
+    oversized: bool,
+}
+
+// Uniform grid that buckets colliders by the cells their bounds touch. Works
+// best when most colliders are about the size of a cell; a collider much
+// larger than a cell ends up in a lot of buckets.
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: f32,
+    entries: Vec<Entry>,
+    // Indices into `entries`
+    cells: HashMap<Cell, Vec<usize>>,
+    // Entries that cover too many cells to be put in the grid. They are
+    // tested against every other entry instead.
+    oversized: Vec<usize>,
+}
+
+impl SpatialHash {
+    const MAX_CELLS_PER_ENTRY: i64 = 1024;
+
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "cell size must be positive");
+        Self {
+            cell_size,
+            entries: Vec::new(),
+            cells: HashMap::new(),
+            oversized: Vec::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn set_cell_size(&mut self, cell_size: f32) {
+        assert!(cell_size > 0.0, "cell size must be positive");
+        self.cell_size = cell_size;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn cell_of(&self, point: Vec2) -> Cell {
+        let cell = (point / self.cell_size).floor();
+        (cell.x as i32, cell.y as i32)
+    }
+}
+
+impl BroadPhase for SpatialHash {
+    fn update(&mut self, colliders: &ColliderSet) {
+        // Everything moves every step in the scenes this is meant for, so the
+        // grid is simply rebuilt. Cleared buckets keep their allocations.
+        self.entries.clear();
+        self.oversized.clear();
+        self.cells.retain(|_, bucket| !bucket.is_empty());
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+
+        for (handle, collider) in colliders.iter() {
+            let aabb = collider.shape.compute_aabb();
+            // NaN bounds never overlap anything
+            if aabb.min.is_nan() || aabb.max.is_nan() {
+                continue;
+            }
+            let index = self.entries.len();
+
+            // Cells saturate at the range of i32, so even infinite bounds
+            // give a finite count here
+            let (min, max) = (self.cell_of(aabb.min), self.cell_of(aabb.max));
+            let cell_count = (max.0 as i64 - min.0 as i64 + 1) * (max.1 as i64 - min.1 as i64 + 1);
+            let oversized = cell_count > Self::MAX_CELLS_PER_ENTRY;
+            self.entries.push(Entry {
+                handle,
+                aabb,
+                oversized,
+            });
+            if oversized {
+                self.oversized.push(index);
+                continue;
+            }
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    self.cells.entry((x, y)).or_default().push(index);
+                }
+            }
+        }
+    }
+
+    fn find_pairs(&self, pairs: &mut Vec<(ColliderHandle, ColliderHandle)>) {
+        for (cell, bucket) in self.cells.iter() {
+            for (i, index1) in bucket.iter().enumerate() {
+                let entry1 = self.entries[*index1];
+                for index2 in bucket[i + 1..].iter() {
+                    let entry2 = self.entries[*index2];
+                    if !entry1.aabb.intersects(entry2.aabb) {
+                        continue;
+                    }
+
+                    // Two colliders can share several cells. Only report the
+                    // pair from the cell holding the min corner of their overlap.
+                    let overlap_min = entry1.aabb.min.max(entry2.aabb.min);
+                    if self.cell_of(overlap_min) != *cell {
+                        continue;
+                    }
+
+                    let (h1, h2) = (entry1.handle, entry2.handle);
+                    pairs.push((h1.min(h2), h1.max(h2)));
+                }
+            }
+        }
+
+        for index1 in self.oversized.iter().copied() {
+            let entry1 = self.entries[index1];
+            for (index2, entry2) in self.entries.iter().enumerate() {
+                // Pairs of oversized entries are reported by the first one
+                if index2 == index1 || (entry2.oversized && index2 < index1) {
+                    continue;
+                }
+                if entry1.aabb.intersects(entry2.aabb) {
+                    let (h1, h2) = (entry1.handle, entry2.handle);
+                    pairs.push((h1.min(h2), h1.max(h2)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collision::{
+            test_utils::{circle_collider, sorted_pairs},
+            Collider, ColliderShape,
+        },
+        Box2D,
+    };
+
+    #[test]
+    fn reports_pairs_spanning_cells_once() {
+        let mut colliders = ColliderSet::new();
+        // Both circles straddle the cell borders at x = 0 and y = 0
+        let a = colliders.insert(circle_collider(-0.5, -0.5));
+        let b = colliders.insert(circle_collider(0.5, 0.5));
+        colliders.insert(circle_collider(10.0, 0.0));
+        // Covers many cells and overlaps `a` and `b` in several of them
+        let c = colliders.insert(Collider::new(ColliderShape::Box2D(Box2D::new(
+            Vec2::new(-5.0, -5.0),
+            Vec2::new(5.0, 5.0),
+            0.0,
+        ))));
+
+        let mut grid = SpatialHash::new(2.0);
+        grid.update(&colliders);
+        let mut expected = vec![
+            (a.min(b), a.max(b)),
+            (a.min(c), a.max(c)),
+            (b.min(c), b.max(c)),
+        ];
+        expected.sort();
+        assert_eq!(sorted_pairs(&grid), expected);
+    }
+
+    #[test]
+    fn keeps_huge_bounds_out_of_the_grid() {
+        let mut colliders = ColliderSet::new();
+        let a = colliders.insert(circle_collider(0.0, 0.0));
+        let b = colliders.insert(circle_collider(1e6, 0.0));
+        // Both span millions of cells
+        let wide = colliders.insert(Collider::new(ColliderShape::Box2D(Box2D::new(
+            Vec2::new(-1e7, -1.0),
+            Vec2::new(1e7, 1.0),
+            0.0,
+        ))));
+        let wide2 = colliders.insert(Collider::new(ColliderShape::Box2D(Box2D::new(
+            Vec2::new(-1e7, 0.5),
+            Vec2::new(1e7, 2.0),
+            0.0,
+        ))));
+        // The bounds of these two come out as NaN
+        colliders.insert(Collider::new(ColliderShape::Box2D(Box2D::new(
+            Vec2::new(f32::NEG_INFINITY, -1.0),
+            Vec2::new(f32::INFINITY, 1.0),
+            0.0,
+        ))));
+        colliders.insert(circle_collider(f32::NAN, 0.0));
+
+        let mut grid = SpatialHash::new(2.0);
+        grid.update(&colliders);
+        let mut expected = vec![
+            (a.min(wide), a.max(wide)),
+            (b.min(wide), b.max(wide)),
+            (a.min(wide2), a.max(wide2)),
+            (b.min(wide2), b.max(wide2)),
+            (wide.min(wide2), wide.max(wide2)),
+        ];
+        expected.sort();
+        assert_eq!(sorted_pairs(&grid), expected);
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use crate::{
+    collision::{ColliderHandle, ColliderSet},
+    Aabb,
+};
+
+use super::BroadPhase;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Entry {
+    handle: ColliderHandle,
+    aabb: Aabb,
+}
+
+// Sort-and-sweep along the x axis. The sorted list is kept between updates,
+// so insertion sort only has to fix up the few colliders that moved past
+// each other.
+#[derive(Debug, Clone, Default)]
+pub struct SweepAndPrune {
+    // Sorted by `aabb.min.x`
+    entries: Vec<Entry>,
+    handles: HashSet<ColliderHandle>,
+}
+
+impl SweepAndPrune {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl BroadPhase for SweepAndPrune {
+    fn update(&mut self, colliders: &ColliderSet) {
+        // Refresh the bounds and forget about removed colliders
+        let handles = &mut self.handles;
+        self.entries
+            .retain_mut(|entry| match colliders.get(entry.handle) {
+                Some(collider) => {
+                    entry.aabb = collider.shape.compute_aabb();
+                    true
+                }
+                None => {
+                    handles.remove(&entry.handle);
+                    false
+                }
+            });
+
+        for (handle, collider) in colliders.iter() {
+            if self.handles.insert(handle) {
+                self.entries.push(Entry {
+                    handle,
+                    aabb: collider.shape.compute_aabb(),
+                });
+            }
+        }
+
+        insertion_sort_by_min_x(&mut self.entries);
+    }
+
+    fn find_pairs(&self, pairs: &mut Vec<(ColliderHandle, ColliderHandle)>) {
+        for (i, entry1) in self.entries.iter().enumerate() {
+            for entry2 in self.entries[i + 1..].iter() {
+                // Every following entry starts even further to the right
+                if entry2.aabb.min.x > entry1.aabb.max.x {
+                    break;
+                }
+
+                if entry1.aabb.intersects(entry2.aabb) {
+                    let (h1, h2) = (entry1.handle, entry2.handle);
+                    pairs.push((h1.min(h2), h1.max(h2)));
+                }
+            }
+        }
+    }
+}
+
+fn insertion_sort_by_min_x(entries: &mut [Entry]) {
+    for i in 1..entries.len() {
+        let mut j = i;
+        while j > 0 && entries[j - 1].aabb.min.x > entries[j].aabb.min.x {
+            entries.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{collision::test_utils::circle_collider, RigidBodySet};
+
+    #[test]
+    fn forgets_removed_colliders() {
+        let mut colliders = ColliderSet::new();
+        let mut bodies = RigidBodySet::new();
+        let a = colliders.insert(circle_collider(0.0, 0.0));
+        colliders.insert(circle_collider(5.0, 0.0));
+
+        let mut sap = SweepAndPrune::new();
+        sap.update(&colliders);
+        assert_eq!(sap.len(), 2);
+        colliders.remove(a, &mut bodies);
+        sap.update(&colliders);
+        assert_eq!(sap.len(), 1);
+    }
+}
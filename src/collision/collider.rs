@@ -1,15 +1,35 @@
+use std::f32::consts::PI;
+
 use glam::Vec2;
 
-use super::algo;
+use super::{algo, gjk, ColliderHandle, ColliderMaterial, CollisionPair, InteractionGroups};
 use crate::{
-    geometry::{Box2D, Circle},
-    RigidBodyHandle,
+    geometry::{
+        Aabb, Box2D, Capsule, Circle, ConvexPolygon, Heightfield, Line2D, Polyline, Ray2D,
+        RaycastResult2D, Segment, SupportMap, TriMesh,
+    },
+    MassProperties, RigidBodyHandle, RigidBodySet,
 };
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Collider {
     pub shape: ColliderShape,
     pub parent: Option<RigidBodyHandle>,
+    // Position and rotation relative to the parent body
+    pub local_position: Vec2,
+    pub local_rotation: f32,
+    // A density of zero means the collider doesn't add to the mass of its parent
+    pub density: f32,
+    // Sensors report overlaps through intersection events but never collide
+    pub is_sensor: bool,
+    pub collision_groups: InteractionGroups,
+    pub material: ColliderMaterial,
+    // Only collide with colliders on the side this direction points to. It
+    // turns with the collider, like `local_position`. Used for one-way
+    // platforms.
+    pub one_way_direction: Option<Vec2>,
+    // Send a `ContactForceEvent` when a contact impulse exceeds this value
+    pub contact_force_event_threshold: Option<f32>,
 }
 
 impl Collider {
@@ -17,11 +37,31 @@ impl Collider {
         Self {
             shape,
             parent: None,
+            local_position: Vec2::ZERO,
+            local_rotation: 0.0,
+            density: 0.0,
+            is_sensor: false,
+            collision_groups: InteractionGroups::ALL,
+            material: ColliderMaterial::default(),
+            one_way_direction: None,
+            contact_force_event_threshold: None,
+        }
+    }
+
+    pub fn with_local_transform(
+        shape: ColliderShape,
+        local_position: Vec2,
+        local_rotation: f32,
+    ) -> Self {
+        Self {
+            local_position,
+            local_rotation,
+            ..Self::new(shape)
         }
     }
 
     pub fn test_collision(&self, other: &Self) -> Option<CollisionManifold> {
-        self.shape.test_collision(other.shape)
+        self.shape.test_collision(&other.shape)
     }
 
     pub fn update_position(&mut self, position: Vec2) {
@@ -32,31 +72,112 @@ impl Collider {
         self.shape.update_rotation(rotation);
     }
 
+    // Places the collider relative to a parent at `position` with `rotation`
+    pub fn update_transform(&mut self, position: Vec2, rotation: f32) {
+        let offset = Vec2::from_angle(rotation).rotate(self.local_position);
+        self.shape.update_position(position + offset);
+        self.shape.update_rotation(rotation + self.local_rotation);
+    }
+
+    pub fn mass_properties(&self) -> MassProperties {
+        let mut props = self.shape.mass_properties(self.density);
+        props.local_center =
+            self.local_position + Vec2::from_angle(self.local_rotation).rotate(props.local_center);
+        props
+    }
+
+    // The rotation of the parent body plus `local_rotation`
+    pub fn world_rotation(&self, bodies: &RigidBodySet) -> f32 {
+        let parent_rotation = self.parent.map_or(0.0, |parent| bodies[parent].rotation());
+        parent_rotation + self.local_rotation
+    }
+
     pub fn set_parent(&mut self, parent: RigidBodyHandle) {
         self.parent = Some(parent);
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+// Polylines, heightfields and meshes share their points between clones, which
+// is why shapes are not `Copy`
+#[derive(Debug, Clone, PartialEq)]
 pub enum ColliderShape {
     Circle(Circle),
     Box2D(Box2D),
+    Polygon(ConvexPolygon),
+    Capsule(Capsule),
+    Segment(Segment),
+    Polyline(Polyline),
+    Heightfield(Heightfield),
+    TriMesh(TriMesh),
 }
 
 impl ColliderShape {
-    pub fn test_collision(self, other: Self) -> Option<CollisionManifold> {
+    pub fn test_collision(&self, other: &Self) -> Option<CollisionManifold> {
         match (self, other) {
-            (ColliderShape::Circle(c1), ColliderShape::Circle(c2)) => algo::circle_circle(c1, c2),
+            // Segments collide like capsules without a radius, polylines and
+            // heightfields like their segments and meshes like their triangles
+            (ColliderShape::Segment(segment), _) => {
+                line_shape(segment.line()).test_collision(other)
+            }
+            (_, ColliderShape::Segment(segment)) => {
+                self.test_collision(&line_shape(segment.line()))
+            }
+            (ColliderShape::Polyline(polyline), _) => algo::polyline_shape(polyline, other),
+            (_, ColliderShape::Polyline(polyline)) => {
+                algo::polyline_shape(polyline, self).map(flipped)
+            }
+            (ColliderShape::Heightfield(heightfield), _) => {
+                algo::heightfield_shape(heightfield, other)
+            }
+            (_, ColliderShape::Heightfield(heightfield)) => {
+                algo::heightfield_shape(heightfield, self).map(flipped)
+            }
+            (ColliderShape::TriMesh(mesh), _) => algo::trimesh_shape(mesh, other),
+            (_, ColliderShape::TriMesh(mesh)) => algo::trimesh_shape(mesh, self).map(flipped),
+            (ColliderShape::Circle(c1), ColliderShape::Circle(c2)) => algo::circle_circle(*c1, *c2),
             (ColliderShape::Circle(circle), ColliderShape::Box2D(box2d)) => {
-                algo::box2d_circle(box2d, circle).map(|mut manifold| {
-                    manifold.normal = -manifold.normal;
-                    manifold
-                })
+                algo::box2d_circle(*box2d, *circle).map(flipped)
             }
             (ColliderShape::Box2D(box2d), ColliderShape::Circle(circle)) => {
-                algo::box2d_circle(box2d, circle)
+                algo::box2d_circle(*box2d, *circle)
+            }
+            (ColliderShape::Box2D(b1), ColliderShape::Box2D(b2)) => algo::box2d_box2d(*b1, *b2),
+            (ColliderShape::Polygon(p1), ColliderShape::Polygon(p2)) => {
+                algo::polygon_polygon(p1, p2)
+            }
+            (ColliderShape::Polygon(polygon), ColliderShape::Circle(circle)) => {
+                algo::polygon_circle(polygon, *circle)
+            }
+            (ColliderShape::Circle(circle), ColliderShape::Polygon(polygon)) => {
+                algo::polygon_circle(polygon, *circle).map(flipped)
+            }
+            (ColliderShape::Polygon(polygon), ColliderShape::Box2D(box2d)) => {
+                algo::polygon_polygon(polygon, &(*box2d).into())
+            }
+            (ColliderShape::Box2D(box2d), ColliderShape::Polygon(polygon)) => {
+                algo::polygon_polygon(&(*box2d).into(), polygon)
+            }
+            (ColliderShape::Capsule(c1), ColliderShape::Capsule(c2)) => {
+                algo::capsule_capsule(*c1, *c2)
+            }
+            (ColliderShape::Capsule(capsule), ColliderShape::Circle(circle)) => {
+                algo::capsule_circle(*capsule, *circle)
+            }
+            (ColliderShape::Circle(circle), ColliderShape::Capsule(capsule)) => {
+                algo::capsule_circle(*capsule, *circle).map(flipped)
+            }
+            (ColliderShape::Polygon(polygon), ColliderShape::Capsule(capsule)) => {
+                algo::polygon_capsule(polygon, *capsule)
+            }
+            (ColliderShape::Capsule(capsule), ColliderShape::Polygon(polygon)) => {
+                algo::polygon_capsule(polygon, *capsule).map(flipped)
+            }
+            (ColliderShape::Box2D(box2d), ColliderShape::Capsule(capsule)) => {
+                algo::polygon_capsule(&(*box2d).into(), *capsule)
+            }
+            (ColliderShape::Capsule(capsule), ColliderShape::Box2D(box2d)) => {
+                algo::polygon_capsule(&(*box2d).into(), *capsule).map(flipped)
             }
-            (ColliderShape::Box2D(b1), ColliderShape::Box2D(b2)) => algo::box2d_box2d(b1, b2),
         }
     }
 
@@ -64,36 +185,454 @@ impl ColliderShape {
         match self {
             ColliderShape::Circle(circle) => circle.center = position,
             ColliderShape::Box2D(box2d) => box2d.set_center(position),
+            ColliderShape::Polygon(polygon) => polygon.position = position,
+            ColliderShape::Capsule(capsule) => capsule.position = position,
+            ColliderShape::Segment(segment) => segment.position = position,
+            ColliderShape::Polyline(polyline) => polyline.position = position,
+            ColliderShape::Heightfield(heightfield) => heightfield.position = position,
+            ColliderShape::TriMesh(mesh) => mesh.position = position,
         }
     }
 
-    fn update_rotation(&mut self, rotation: f32) {
+    pub fn update_rotation(&mut self, rotation: f32) {
         match self {
-            ColliderShape::Circle(_) => (),
+            ColliderShape::Circle(_) | ColliderShape::Heightfield(_) => (),
             ColliderShape::Box2D(box2d) => box2d.rotation = rotation,
+            ColliderShape::Polygon(polygon) => polygon.rotation = rotation,
+            ColliderShape::Capsule(capsule) => capsule.rotation = rotation,
+            ColliderShape::Segment(segment) => segment.rotation = rotation,
+            ColliderShape::Polyline(polyline) => polyline.rotation = rotation,
+            ColliderShape::TriMesh(mesh) => mesh.rotation = rotation,
+        }
+    }
+
+    // Sweeps this shape by `velocity` against `other`, which stays in place.
+    // Shapes that already overlap are hit at a time of impact of zero.
+    // Polylines and heightfields are only hit on the side their segments
+    // face, like in `test_collision`.
+    pub fn cast_shape(
+        &self,
+        velocity: Vec2,
+        other: &Self,
+        max_toi: f32,
+    ) -> Option<ShapeCastResult2D> {
+        // Only segments the other shape can reach during the cast
+        let displacement = velocity * max_toi;
+        let other_bounds = other.compute_swept_aabb(-displacement);
+        if let Some(parts) = self.parts(Some(other_bounds), other.center()) {
+            return parts
+                .iter()
+                .filter_map(|part| part.cast_shape(velocity, other, max_toi))
+                .min_by(|a, b| a.toi.total_cmp(&b.toi));
+        }
+        let self_bounds = self.compute_swept_aabb(displacement);
+        if let Some(parts) = other.parts(Some(self_bounds), self.center()) {
+            return parts
+                .iter()
+                .filter_map(|part| self.cast_shape(velocity, part, max_toi))
+                .min_by(|a, b| a.toi.total_cmp(&b.toi));
+        }
+
+        match (self, other) {
+            (ColliderShape::Circle(c1), ColliderShape::Circle(c2)) => {
+                algo::cast_circle_circle(*c1, velocity, *c2, max_toi)
+            }
+            (ColliderShape::Circle(circle), ColliderShape::Box2D(box2d)) => {
+                algo::cast_circle_box2d(*circle, velocity, *box2d, max_toi)
+            }
+            (ColliderShape::Box2D(box2d), ColliderShape::Circle(circle)) => {
+                algo::cast_box2d_circle(*box2d, velocity, *circle, max_toi)
+            }
+            (ColliderShape::Box2D(b1), ColliderShape::Box2D(b2)) => {
+                algo::cast_convex_convex(*b1, velocity, *b2, max_toi)
+            }
+            (ColliderShape::Polygon(p1), ColliderShape::Polygon(p2)) => {
+                algo::cast_convex_convex(*p1, velocity, *p2, max_toi)
+            }
+            (ColliderShape::Polygon(polygon), ColliderShape::Box2D(box2d)) => {
+                algo::cast_convex_convex(*polygon, velocity, *box2d, max_toi)
+            }
+            (ColliderShape::Box2D(box2d), ColliderShape::Polygon(polygon)) => {
+                algo::cast_convex_convex(*box2d, velocity, *polygon, max_toi)
+            }
+            _ => {
+                if let Some(manifold) = self.test_collision(other) {
+                    return Some(ShapeCastResult2D {
+                        point: manifold.contact_point_b,
+                        normal: -manifold.normal,
+                        toi: 0.0,
+                    });
+                }
+
+                self.with_rounded_core(|core1, radius1| {
+                    other.with_rounded_core(|core2, radius2| {
+                        gjk::cast(core1, radius1, velocity, core2, radius2, max_toi)
+                    })
+                })
+            }
+        }
+    }
+
+    pub fn closest_points(&self, other: &Self) -> gjk::ClosestPoints {
+        if let Some(parts) = self.parts(None, other.center()) {
+            return closest_of(parts.iter().map(|part| part.closest_points(other)));
+        }
+        if let Some(parts) = other.parts(None, self.center()) {
+            return closest_of(parts.iter().map(|part| self.closest_points(part)));
+        }
+
+        self.with_rounded_core(|core1, radius1| {
+            other.with_rounded_core(|core2, radius2| {
+                gjk::closest_points(core1, radius1, core2, radius2)
+            })
+        })
+    }
+
+    // `point1` of the result is `point`, `point2` is on the shape
+    pub fn closest_points_to_point(&self, point: Vec2) -> gjk::ClosestPoints {
+        if let Some(parts) = self.parts(None, point) {
+            return closest_of(parts.iter().map(|part| part.closest_points_to_point(point)));
+        }
+
+        self.with_rounded_core(|core, radius| gjk::closest_points(&point, 0.0, core, radius))
+    }
+
+    // Polylines, heightfields and meshes are made of convex parts that collide
+    // one at a time. Only the parts overlapping `bounds` are returned, if given,
+    // and only the segments facing `facing`, the center of the other shape or
+    // the query point.
+    fn parts(&self, bounds: Option<Aabb>, facing: Vec2) -> Option<Vec<ColliderShape>> {
+        let parts: Vec<_> = match self {
+            ColliderShape::Polyline(p) => p
+                .segments()
+                .filter(|segment| segment.faces(facing))
+                .map(|segment| line_shape(segment.line))
+                .collect(),
+            ColliderShape::Heightfield(h) => {
+                let x_range = bounds.unwrap_or_else(|| h.aabb());
+                h.segments_in_x_range(x_range.min.x, x_range.max.x)
+                    .filter(|segment| segment.faces(facing))
+                    .map(|segment| line_shape(segment.line))
+                    .collect()
+            }
+            ColliderShape::TriMesh(m) => {
+                let mut triangles = Vec::new();
+                m.triangles_in_aabb(bounds.unwrap_or_else(|| m.aabb()), |i| {
+                    triangles.push(ColliderShape::Polygon(m.triangle(i)));
+                    true
+                });
+                triangles
+            }
+            _ => return None,
+        };
+        Some(
+            parts
+                .into_iter()
+                .filter(|part| bounds.is_none_or(|bounds| part.compute_aabb().intersects(bounds)))
+                .collect(),
+        )
+    }
+
+    // Passes the shape as a convex core grown by a radius to `f`, for GJK.
+    // Polylines, heightfields and meshes only give their convex hull, so they
+    // have to be split into parts first.
+    fn with_rounded_core<R>(&self, f: impl FnOnce(&dyn SupportMap, f32) -> R) -> R {
+        match self {
+            ColliderShape::Circle(c) => f(&c.center, c.radius),
+            ColliderShape::Box2D(b) => f(b, 0.0),
+            ColliderShape::Polygon(p) => f(p, 0.0),
+            ColliderShape::Capsule(c) => f(&c.segment(), c.radius),
+            ColliderShape::Segment(s) => f(&s.line(), 0.0),
+            ColliderShape::Polyline(p) => f(p, 0.0),
+            ColliderShape::Heightfield(h) => f(h, 0.0),
+            ColliderShape::TriMesh(m) => f(m, 0.0),
         }
     }
 
-    pub fn center(self) -> Vec2 {
+    // Segments, polylines and heightfields have no inside
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        match self {
+            ColliderShape::Circle(c) => algo::is_point_in_circle(point, *c),
+            ColliderShape::Box2D(b) => algo::is_point_in_box2d(point, *b),
+            ColliderShape::Polygon(p) => algo::is_point_in_polygon(point, p),
+            ColliderShape::TriMesh(m) => algo::is_point_in_trimesh(point, m),
+            ColliderShape::Capsule(c) => {
+                let closest = algo::closest_point_on_segment(point, c.segment());
+                closest.distance_squared(point) <= c.radius * c.radius
+            }
+            ColliderShape::Segment(_)
+            | ColliderShape::Polyline(_)
+            | ColliderShape::Heightfield(_) => false,
+        }
+    }
+
+    // A `solid` shape stops rays that start inside it right away, with a zero
+    // normal. Otherwise the ray hits the boundary on its way out.
+    pub fn cast_ray(&self, ray: Ray2D, max_t: f32, solid: bool) -> Option<RaycastResult2D> {
+        if solid && self.contains_point(ray.origin) {
+            return Some(RaycastResult2D {
+                point: ray.origin,
+                normal: Vec2::ZERO,
+                t: 0.0,
+            });
+        }
+
+        algo::raycast_shape(ray, self).filter(|hit| hit.t <= max_t)
+    }
+
+    pub fn center(&self) -> Vec2 {
         match self {
             ColliderShape::Circle(c) => c.center,
             ColliderShape::Box2D(b) => b.center(),
+            ColliderShape::Polygon(p) => p.centroid(),
+            ColliderShape::Capsule(c) => c.center(),
+            ColliderShape::Segment(s) => {
+                let line = s.line();
+                (line.start + line.end) / 2.0
+            }
+            ColliderShape::Polyline(p) => p.position,
+            ColliderShape::Heightfield(h) => h.aabb().center(),
+            ColliderShape::TriMesh(m) => m.aabb().center(),
+        }
+    }
+
+    pub fn compute_aabb(&self) -> Aabb {
+        match self {
+            ColliderShape::Circle(c) => (*c).into(),
+            ColliderShape::Box2D(b) => (*b).into(),
+            ColliderShape::Polygon(p) => Aabb::from_points(p.vertices()),
+            ColliderShape::Capsule(c) => {
+                let segment = c.segment();
+                Aabb::from_points([segment.start, segment.end]).loosened(c.radius)
+            }
+            ColliderShape::Segment(s) => {
+                let line = s.line();
+                Aabb::from_points([line.start, line.end])
+            }
+            ColliderShape::Polyline(p) => Aabb::from_points(p.vertices()),
+            ColliderShape::Heightfield(h) => h.aabb(),
+            ColliderShape::TriMesh(m) => m.aabb(),
+        }
+    }
+
+    // Bounds of the shape over a move by `displacement`
+    pub fn compute_swept_aabb(&self, displacement: Vec2) -> Aabb {
+        self.compute_aabb().swept(displacement)
+    }
+
+    // Bounds grown by `margin` on every side, so small moves and turns stay
+    // inside them
+    pub fn compute_fattened_aabb(&self, margin: f32) -> Aabb {
+        self.compute_aabb().loosened(margin)
+    }
+
+    // The center of mass is relative to the position of the shape, unrotated.
+    // Segments, polylines and heightfields have no area and so no mass.
+    pub fn mass_properties(&self, density: f32) -> MassProperties {
+        match self {
+            ColliderShape::Circle(c) => {
+                let mass = density * PI * c.radius * c.radius;
+                MassProperties::new(mass, Vec2::ZERO, mass * c.radius * c.radius / 2.0)
+            }
+            ColliderShape::Box2D(b) => {
+                let size = b.size();
+                let mass = density * size.x * size.y;
+                MassProperties::new(mass, Vec2::ZERO, mass * size.length_squared() / 12.0)
+            }
+            ColliderShape::Polygon(p) => {
+                let (area, centroid, inertia) = p.area_centroid_and_inertia();
+                MassProperties::new(density * area, centroid, density * inertia)
+            }
+            ColliderShape::Capsule(c) => {
+                let (area, centroid, inertia) = c.area_centroid_and_inertia();
+                MassProperties::new(density * area, centroid, density * inertia)
+            }
+            ColliderShape::TriMesh(m) => m
+                .local_triangles()
+                .iter()
+                .map(|triangle| ColliderShape::Polygon(*triangle).mass_properties(density))
+                .sum(),
+            ColliderShape::Segment(_)
+            | ColliderShape::Polyline(_)
+            | ColliderShape::Heightfield(_) => MassProperties::default(),
         }
     }
 }
 
+// The furthest point of the shape in `direction`
+impl SupportMap for ColliderShape {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        self.with_rounded_core(|core, radius| {
+            core.support_point(direction) + direction.normalize_or_zero() * radius
+        })
+    }
+}
+
+// A world space line as a shape, a capsule without a radius
+fn line_shape(line: Line2D) -> ColliderShape {
+    ColliderShape::Capsule(Capsule::new(line.start, line.end, 0.0))
+}
+
+fn closest_of(results: impl Iterator<Item = gjk::ClosestPoints>) -> gjk::ClosestPoints {
+    results
+        .min_by(|a, b| a.distance().total_cmp(&b.distance()))
+        .unwrap_or(gjk::ClosestPoints::Unreachable)
+}
+
+// Swaps the roles of the two shapes of a manifold
+fn flipped(manifold: CollisionManifold) -> CollisionManifold {
+    CollisionManifold {
+        normal: -manifold.normal,
+        contact_point_a: manifold.contact_point_b,
+        contact_point_b: manifold.contact_point_a,
+        depth: manifold.depth,
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Collision {
-    pub body_handle1: RigidBodyHandle,
-    pub body_handle2: RigidBodyHandle,
+    // `None` for colliders without a parent body
+    pub body_handle1: Option<RigidBodyHandle>,
+    pub body_handle2: Option<RigidBodyHandle>,
+    pub collider_handle1: ColliderHandle,
+    pub collider_handle2: ColliderHandle,
     pub manifold: CollisionManifold,
+    // Combined material of both colliders, used by the solver
+    pub friction: f32,
+    pub restitution: f32,
+    // Velocity of the surface of the second collider relative to the first one
+    pub surface_velocity: Vec2,
+    // Total impulses applied along the normal and the tangent, which is
+    // `normal.perp()`, during the last step
+    pub normal_impulse: f32,
+    pub tangent_impulse: f32,
+}
+
+impl Collision {
+    // The normal and tangent impulses are at right angles to each other
+    pub fn total_impulse(&self) -> f32 {
+        self.normal_impulse.hypot(self.tangent_impulse)
+    }
+
+    pub fn pair(&self) -> CollisionPair {
+        CollisionPair {
+            collider_handle1: self.collider_handle1,
+            collider_handle2: self.collider_handle2,
+            body_handle1: self.body_handle1,
+            body_handle2: self.body_handle2,
+        }
+    }
+}
+
+// First contact of a shape swept along a velocity. `point` and `normal` are
+// on the shape that was hit, with the normal pointing towards the moving shape.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeCastResult2D {
+    pub point: Vec2,
+    pub normal: Vec2,
+    // Fraction of the velocity the moving shape travels before the contact
+    pub toi: f32,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CollisionManifold {
     pub normal: Vec2,
-    // TODO: Actually use contant points and depth
+    // Deepest points of each shape inside the other. The solver applies
+    // impulses halfway between them and uses `depth` to push bodies apart.
     pub contact_point_a: Vec2,
     pub contact_point_b: Vec2,
     pub depth: f32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(x: f32, y: f32) -> ColliderShape {
+        ColliderShape::Circle(Circle::new(Vec2::new(x, y), 0.5))
+    }
+
+    // Both collide with shapes on their -y side
+    fn one_sided_grounds() -> [ColliderShape; 2] {
+        [
+            ColliderShape::Polyline(
+                Polyline::new(vec![
+                    Vec2::new(0.0, 0.0),
+                    Vec2::new(5.0, 0.0),
+                    Vec2::new(10.0, 0.0),
+                ])
+                .unwrap(),
+            ),
+            ColliderShape::Heightfield(
+                Heightfield::new(vec![0.0; 3], Vec2::new(5.0, 1.0)).unwrap(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn swept_and_fattened_bounds() {
+        let shape = circle(1.0, 2.0);
+        assert_eq!(
+            shape.compute_swept_aabb(Vec2::new(3.0, -1.0)),
+            Aabb::new(Vec2::new(0.5, 0.5), Vec2::new(4.5, 2.5))
+        );
+        assert_eq!(shape.compute_swept_aabb(Vec2::ZERO), shape.compute_aabb());
+        assert_eq!(
+            shape.compute_fattened_aabb(0.25),
+            Aabb::new(Vec2::new(0.25, 1.25), Vec2::new(1.75, 2.75))
+        );
+    }
+
+    #[test]
+    fn one_sided_shapes_are_only_cast_against_from_the_front() {
+        for ground in one_sided_grounds() {
+            let hit = circle(5.0, -2.0)
+                .cast_shape(Vec2::Y, &ground, 10.0)
+                .unwrap();
+            assert!((hit.toi - 1.5).abs() < 1e-4, "{ground:?}");
+            assert!(hit.normal.abs_diff_eq(Vec2::NEG_Y, 1e-4), "{ground:?}");
+            // The ground moving into the circle hits it the same way
+            let hit = ground.cast_shape(Vec2::NEG_Y, &circle(5.0, -2.0), 10.0);
+            assert!((hit.unwrap().toi - 1.5).abs() < 1e-4, "{ground:?}");
+
+            assert!(circle(5.0, 2.0)
+                .cast_shape(Vec2::NEG_Y, &ground, 10.0)
+                .is_none());
+            assert!(ground
+                .cast_shape(Vec2::Y, &circle(5.0, 2.0), 10.0)
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn one_sided_shapes_agree_with_test_collision() {
+        for ground in one_sided_grounds() {
+            // Overlapping from behind is no contact and not hit either
+            let behind = circle(5.0, 0.3);
+            assert!(ground.test_collision(&behind).is_none());
+            assert!(behind.cast_shape(Vec2::ZERO, &ground, 1.0).is_none());
+            assert!(!ground.closest_points(&behind).is_overlapping());
+            assert!(!behind.closest_points(&ground).is_overlapping());
+
+            let in_front = circle(5.0, -0.3);
+            assert!(ground.test_collision(&in_front).is_some());
+            let hit = in_front.cast_shape(Vec2::ZERO, &ground, 1.0).unwrap();
+            assert_eq!(hit.toi, 0.0);
+            assert!(ground.closest_points(&in_front).is_overlapping());
+        }
+    }
+
+    #[test]
+    fn one_sided_shapes_are_unreachable_from_behind() {
+        for ground in one_sided_grounds() {
+            let result = ground.closest_points(&circle(5.0, 2.0));
+            assert_eq!(result, gjk::ClosestPoints::Unreachable, "{ground:?}");
+            let result = ground.closest_points_to_point(Vec2::new(5.0, 2.0));
+            assert_eq!(result, gjk::ClosestPoints::Unreachable, "{ground:?}");
+
+            assert!((ground.closest_points(&circle(5.0, -2.0)).distance() - 1.5).abs() < 1e-4);
+            let result = ground.closest_points_to_point(Vec2::new(5.0, -2.0));
+            assert!((result.distance() - 2.0).abs() < 1e-4);
+        }
+    }
+}
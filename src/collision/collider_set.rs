@@ -27,10 +27,10 @@ impl ColliderSet {
     ) -> ColliderHandle {
         let body = &mut bodies[body_handle];
         collider.parent = Some(body_handle);
-        collider.update_position(body.position);
-        collider.update_rotation(body.rotation);
+        collider.update_transform(body.position, body.rotation);
         let collider_handle = self.insert(collider);
-        body.collider = Some(collider_handle);
+        body.colliders.push(collider_handle);
+        body.recompute_mass_properties(self);
         collider_handle
     }
 
@@ -40,8 +40,9 @@ impl ColliderSet {
         bodies: &mut RigidBodySet,
     ) -> Option<Collider> {
         let collider = self.colliders.remove(handle.0)?;
-        if let Some(parent_handle) = collider.parent {
-            bodies[parent_handle].collider = None;
+        if let Some(body) = collider.parent.and_then(|parent| bodies.get_mut(parent)) {
+            body.colliders.retain(|h| *h != handle);
+            body.recompute_mass_properties(self);
         }
 
         Some(collider)
@@ -96,5 +97,5 @@ impl std::ops::IndexMut<ColliderHandle> for ColliderSet {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ColliderHandle(pub Index);
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use crate::RigidBodyHandle;
+
+use super::ColliderHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CollisionPair {
+    pub collider_handle1: ColliderHandle,
+    pub collider_handle2: ColliderHandle,
+    // `None` for colliders without a parent body
+    pub body_handle1: Option<RigidBodyHandle>,
+    pub body_handle2: Option<RigidBodyHandle>,
+}
+
+impl CollisionPair {
+    pub(crate) fn key(&self) -> (ColliderHandle, ColliderHandle) {
+        let (h1, h2) = (self.collider_handle1, self.collider_handle2);
+        (h1.min(h2), h1.max(h2))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionEvent {
+    // The colliders touched for the first time this step
+    Started(CollisionPair),
+    // The colliders were already touching on the previous step. Only sent
+    // when turned on with `PhysicsPipeline::set_persisted_collision_events`.
+    Persisted(CollisionPair),
+    // The colliders stopped touching, or one of them was removed
+    Stopped(CollisionPair),
+}
+
+impl CollisionEvent {
+    pub fn pair(&self) -> CollisionPair {
+        match self {
+            CollisionEvent::Started(pair)
+            | CollisionEvent::Persisted(pair)
+            | CollisionEvent::Stopped(pair) => *pair,
+        }
+    }
+
+    pub fn started(&self) -> bool {
+        matches!(self, CollisionEvent::Started(_))
+    }
+
+    pub fn stopped(&self) -> bool {
+        matches!(self, CollisionEvent::Stopped(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntersectionEvent {
+    // A sensor started overlapping another collider
+    Started(CollisionPair),
+    // A sensor stopped overlapping another collider, or one of them was removed
+    Stopped(CollisionPair),
+}
+
+impl IntersectionEvent {
+    pub fn pair(&self) -> CollisionPair {
+        match self {
+            IntersectionEvent::Started(pair) | IntersectionEvent::Stopped(pair) => *pair,
+        }
+    }
+
+    pub fn started(&self) -> bool {
+        matches!(self, IntersectionEvent::Started(_))
+    }
+
+    pub fn stopped(&self) -> bool {
+        matches!(self, IntersectionEvent::Stopped(_))
+    }
+}
+
+// Sent when the impulse applied between two colliders during a step exceeds
+// the `contact_force_event_threshold` of either of them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactForceEvent {
+    pub pair: CollisionPair,
+    // Magnitude of the normal and tangent impulses together
+    pub total_impulse: f32,
+    pub total_normal_impulse: f32,
+    // Along the tangent, which is the contact normal turned a quarter turn
+    // counter-clockwise, so it can be negative
+    pub total_tangent_impulse: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PairStatus {
+    Started,
+    Persisted,
+    Stopped,
+}
+
+// Remembers which pairs were touching on the previous step
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PairTracker {
+    active_pairs: HashMap<(ColliderHandle, ColliderHandle), CollisionPair>,
+}
+
+impl PairTracker {
+    pub(crate) fn update(
+        &mut self,
+        pairs: impl Iterator<Item = CollisionPair>,
+        mut on_status: impl FnMut(PairStatus, CollisionPair),
+    ) {
+        let mut previous_pairs = std::mem::take(&mut self.active_pairs);
+        for pair in pairs {
+            if previous_pairs.remove(&pair.key()).is_some() {
+                on_status(PairStatus::Persisted, pair);
+            } else {
+                on_status(PairStatus::Started, pair);
+            }
+            self.active_pairs.insert(pair.key(), pair);
+        }
+
+        let mut stopped: Vec<_> = previous_pairs.into_values().collect();
+        stopped.sort_by_key(|pair| pair.key());
+        for pair in stopped {
+            on_status(PairStatus::Stopped, pair);
+        }
+    }
+
+    pub(crate) fn contains(&self, pair: &CollisionPair) -> bool {
+        self.active_pairs.contains_key(&pair.key())
+    }
+}
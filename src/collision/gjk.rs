@@ -0,0 +1,580 @@
+use glam::Vec2;
+
+use crate::geometry::SupportMap;
+
+use super::{CollisionManifold, ShapeCastResult2D};
+
+const MAX_ITERATIONS: usize = 32;
+// Relative progress below which the search has converged
+const RELATIVE_TOLERANCE: f32 = 1e-5;
+// Distances below this count as touching
+const OVERLAP_TOLERANCE: f32 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClosestPoints {
+    Overlapping,
+    Separated {
+        distance: f32,
+        // Witness points, on the first and second shape
+        point1: Vec2,
+        point2: Vec2,
+    },
+    // Nothing of the second shape can be reached from the first, like a
+    // shape behind every segment of a polyline
+    Unreachable,
+}
+
+impl ClosestPoints {
+    pub fn distance(self) -> f32 {
+        match self {
+            ClosestPoints::Overlapping => 0.0,
+            ClosestPoints::Separated { distance, .. } => distance,
+            ClosestPoints::Unreachable => f32::INFINITY,
+        }
+    }
+
+    pub fn is_overlapping(self) -> bool {
+        self == ClosestPoints::Overlapping
+    }
+}
+
+// A point of the Minkowski difference `shape1 - shape2`, remembering the
+// points of both shapes it came from
+#[derive(Debug, Clone, Copy)]
+struct SimplexVertex {
+    point1: Vec2,
+    point2: Vec2,
+    point: Vec2,
+}
+
+impl SimplexVertex {
+    fn new(
+        shape1: &(impl SupportMap + ?Sized),
+        shape2: &(impl SupportMap + ?Sized),
+        direction: Vec2,
+    ) -> Self {
+        let point1 = shape1.support_point(direction);
+        let point2 = shape2.support_point(-direction);
+        Self {
+            point1,
+            point2,
+            point: point1 - point2,
+        }
+    }
+}
+
+// Closest points between two convex shapes, each grown by a radius. Rounded
+// shapes like circles are passed as their center and radius, which converges
+// much faster than sampling their curved outline.
+pub fn closest_points(
+    shape1: &(impl SupportMap + ?Sized),
+    radius1: f32,
+    shape2: &(impl SupportMap + ?Sized),
+    radius2: f32,
+) -> ClosestPoints {
+    let Ok((point1, point2)) = gjk(shape1, shape2) else {
+        return ClosestPoints::Overlapping;
+    };
+
+    let distance = point1.distance(point2) - radius1 - radius2;
+    if distance <= OVERLAP_TOLERANCE {
+        return ClosestPoints::Overlapping;
+    }
+
+    let normal = (point2 - point1).normalize_or_zero();
+    ClosestPoints::Separated {
+        distance,
+        point1: point1 + normal * radius1,
+        point2: point2 - normal * radius2,
+    }
+}
+
+pub fn distance(
+    shape1: &(impl SupportMap + ?Sized),
+    radius1: f32,
+    shape2: &(impl SupportMap + ?Sized),
+    radius2: f32,
+) -> f32 {
+    closest_points(shape1, radius1, shape2, radius2).distance()
+}
+
+// Conservative advancement: moves the first shape along `velocity` by as much
+// as it can go without passing through the second one, until they touch.
+// Returns a zero normal if the shapes already overlap.
+pub fn cast(
+    shape1: &(impl SupportMap + ?Sized),
+    radius1: f32,
+    velocity: Vec2,
+    shape2: &(impl SupportMap + ?Sized),
+    radius2: f32,
+    max_toi: f32,
+) -> Option<ShapeCastResult2D> {
+    const CAST_TOLERANCE: f32 = 1e-4;
+
+    let mut toi = 0.0;
+    // Closest point and normal from the previous iteration, for when the
+    // shapes end up touching at the new time of impact
+    let mut point = shape2.support_point(-velocity);
+    let mut normal = Vec2::ZERO;
+    for _ in 0..MAX_ITERATIONS {
+        let moved_shape1 = Translated {
+            shape: shape1,
+            offset: velocity * toi,
+        };
+        let ClosestPoints::Separated {
+            distance,
+            point1,
+            point2,
+        } = closest_points(&moved_shape1, radius1, shape2, radius2)
+        else {
+            break;
+        };
+
+        point = point2;
+        normal = (point1 - point2).normalize_or_zero();
+        if distance <= CAST_TOLERANCE {
+            break;
+        }
+
+        let approach_speed = -velocity.dot(normal);
+        if approach_speed <= 0.0 {
+            return None;
+        }
+        toi += distance / approach_speed;
+        if toi > max_toi {
+            return None;
+        }
+    }
+
+    Some(ShapeCastResult2D { point, normal, toi })
+}
+
+struct Translated<'a, S: SupportMap + ?Sized> {
+    shape: &'a S,
+    offset: Vec2,
+}
+
+impl<S: SupportMap + ?Sized> SupportMap for Translated<'_, S> {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        self.shape.support_point(direction) + self.offset
+    }
+}
+
+// Contact between two convex shapes, each grown by a radius, for any pair of
+// shapes. The normal points from the first shape to the second.
+//
+// While the shapes without their radii are apart, the contact is between their
+// closest points. Once those overlap too, EPA finds how far apart they have to
+// be pushed.
+pub fn contact(
+    shape1: &(impl SupportMap + ?Sized),
+    radius1: f32,
+    shape2: &(impl SupportMap + ?Sized),
+    radius2: f32,
+) -> Option<CollisionManifold> {
+    let (normal, depth, point1, point2) = match gjk(shape1, shape2) {
+        Ok((point1, point2)) => {
+            let distance = point1.distance(point2);
+            if distance > radius1 + radius2 {
+                return None;
+            }
+            let normal = (point2 - point1) / distance;
+            (normal, -distance, point1, point2)
+        }
+        Err(simplex) => {
+            let touching = simplex[0];
+            match epa(shape1, shape2, simplex) {
+                Some(contact) => contact,
+                // The shapes without their radii only meet at a point, so any
+                // direction pushes them apart
+                None if radius1 + radius2 > 0.0 => (Vec2::Y, 0.0, touching.point1, touching.point2),
+                None => return None,
+            }
+        }
+    };
+
+    Some(CollisionManifold {
+        normal,
+        contact_point_a: point1 + normal * radius1,
+        contact_point_b: point2 - normal * radius2,
+        depth: depth + radius1 + radius2,
+    })
+}
+
+// The closest points, or the simplex that holds the origin if the shapes
+// overlap
+fn gjk(
+    shape1: &(impl SupportMap + ?Sized),
+    shape2: &(impl SupportMap + ?Sized),
+) -> Result<(Vec2, Vec2), Vec<SimplexVertex>> {
+    let mut simplex = vec![SimplexVertex::new(shape1, shape2, Vec2::X)];
+    let mut weights = vec![1.0];
+
+    for _ in 0..MAX_ITERATIONS {
+        let closest = weighted_sum(&simplex, &weights, |vertex| vertex.point);
+        let distance_squared = closest.length_squared();
+        if distance_squared <= OVERLAP_TOLERANCE * OVERLAP_TOLERANCE {
+            return Err(simplex);
+        }
+
+        // Look for a point of the Minkowski difference closer to the origin
+        let vertex = SimplexVertex::new(shape1, shape2, -closest);
+        let progress = distance_squared - closest.dot(vertex.point);
+        let is_duplicate = simplex
+            .iter()
+            .any(|existing| existing.point == vertex.point);
+        if progress <= RELATIVE_TOLERANCE * distance_squared || is_duplicate {
+            break;
+        }
+
+        simplex.push(vertex);
+        if !reduce_simplex(&mut simplex, &mut weights) {
+            return Err(simplex);
+        }
+    }
+
+    Ok((
+        weighted_sum(&simplex, &weights, |vertex| vertex.point1),
+        weighted_sum(&simplex, &weights, |vertex| vertex.point2),
+    ))
+}
+
+// Expanding polytope algorithm: grows a polygon inside the Minkowski difference
+// from the simplex GJK ended with, towards its edge closest to the origin.
+// Returns the normal from the first shape to the second, the depth and the
+// deepest points of both shapes, or `None` if the polytope has no edge to take
+// a normal from.
+fn epa(
+    shape1: &(impl SupportMap + ?Sized),
+    shape2: &(impl SupportMap + ?Sized),
+    simplex: Vec<SimplexVertex>,
+) -> Option<(Vec2, f32, Vec2, Vec2)> {
+    let mut polytope = simplex;
+    // GJK stops early when the origin is on a vertex or an edge, so make a
+    // triangle first
+    if polytope.len() == 1 {
+        let direction = if polytope[0].point.x > 0.0 {
+            Vec2::NEG_X
+        } else {
+            Vec2::X
+        };
+        polytope.push(SimplexVertex::new(shape1, shape2, direction));
+    }
+    if polytope.len() == 2 {
+        let edge = polytope[1].point - polytope[0].point;
+        let side = edge.perp();
+        let vertex = [side, -side]
+            .map(|direction| SimplexVertex::new(shape1, shape2, direction))
+            .into_iter()
+            .max_by(|a, b| {
+                let height_a = edge.perp_dot(a.point - polytope[0].point).abs();
+                let height_b = edge.perp_dot(b.point - polytope[0].point).abs();
+                height_a.total_cmp(&height_b)
+            })
+            .unwrap();
+        polytope.push(vertex);
+    }
+    // Counter-clockwise, so edge normals point out to the right
+    if (polytope[1].point - polytope[0].point).perp_dot(polytope[2].point - polytope[0].point) < 0.0
+    {
+        polytope.swap(1, 2);
+    }
+
+    let mut closest = None;
+    for _ in 0..MAX_ITERATIONS {
+        // Edges between points that coincide have no normal
+        let Some(edge) = (0..polytope.len())
+            .filter_map(|i| {
+                let a = polytope[i].point;
+                let b = polytope[(i + 1) % polytope.len()].point;
+                let edge = b - a;
+                let normal = Vec2::new(edge.y, -edge.x).try_normalize()?;
+                Some((normal, normal.dot(a), i))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            break;
+        };
+        closest = Some(edge);
+        let (normal, distance, i) = edge;
+
+        let vertex = SimplexVertex::new(shape1, shape2, normal);
+        let is_duplicate = polytope
+            .iter()
+            .any(|existing| existing.point == vertex.point);
+        if vertex.point.dot(normal) - distance
+            <= RELATIVE_TOLERANCE.max(distance * RELATIVE_TOLERANCE)
+            || is_duplicate
+        {
+            break;
+        }
+        polytope.insert(i + 1, vertex);
+    }
+
+    // The closest point on the edge tells how to mix the points of the shapes
+    let (normal, distance, i) = closest?;
+    let a = polytope[i];
+    let b = polytope[(i + 1) % polytope.len()];
+    let edge = b.point - a.point;
+    let t = (-a.point.dot(edge) / edge.length_squared()).clamp(0.0, 1.0);
+    let t = if t.is_finite() { t } else { 0.0 };
+    Some((
+        normal,
+        distance,
+        a.point1.lerp(b.point1, t),
+        a.point2.lerp(b.point2, t),
+    ))
+}
+
+fn weighted_sum(
+    simplex: &[SimplexVertex],
+    weights: &[f32],
+    point: impl Fn(&SimplexVertex) -> Vec2,
+) -> Vec2 {
+    simplex
+        .iter()
+        .zip(weights)
+        .map(|(vertex, weight)| point(vertex) * *weight)
+        .sum()
+}
+
+// Keeps the smallest part of the simplex that holds the point closest to the
+// origin, and sets the barycentric weights of that point. Returns false if the
+// origin is inside the simplex.
+fn reduce_simplex(simplex: &mut Vec<SimplexVertex>, weights: &mut Vec<f32>) -> bool {
+    let (kept, new_weights): (Vec<usize>, Vec<f32>) = match simplex.len() {
+        2 => {
+            let (a, b) = (simplex[0].point, simplex[1].point);
+            let ab = b - a;
+            let t = -a.dot(ab) / ab.length_squared();
+            if t <= 0.0 {
+                (vec![0], vec![1.0])
+            } else if t >= 1.0 {
+                (vec![1], vec![1.0])
+            } else {
+                (vec![0, 1], vec![1.0 - t, t])
+            }
+        }
+        3 => match closest_on_triangle(simplex[0].point, simplex[1].point, simplex[2].point) {
+            Some(region) => region,
+            None => return false,
+        },
+        _ => unreachable!("simplex has {} vertices", simplex.len()),
+    };
+
+    *simplex = kept.iter().map(|i| simplex[*i]).collect();
+    *weights = new_weights;
+    true
+}
+
+// Voronoi region test for the point of triangle `abc` closest to the origin.
+// Returns the vertices of the feature holding it and their weights, or None
+// if the origin is inside the triangle.
+fn closest_on_triangle(a: Vec2, b: Vec2, c: Vec2) -> Option<(Vec<usize>, Vec<f32>)> {
+    let ab = b - a;
+    let ac = c - a;
+    let d1 = ab.dot(-a);
+    let d2 = ac.dot(-a);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return Some((vec![0], vec![1.0]));
+    }
+
+    let d3 = ab.dot(-b);
+    let d4 = ac.dot(-b);
+    if d3 >= 0.0 && d4 <= d3 {
+        return Some((vec![1], vec![1.0]));
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let t = d1 / (d1 - d3);
+        return Some((vec![0, 1], vec![1.0 - t, t]));
+    }
+
+    let d5 = ab.dot(-c);
+    let d6 = ac.dot(-c);
+    if d6 >= 0.0 && d5 <= d6 {
+        return Some((vec![2], vec![1.0]));
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let t = d2 / (d2 - d6);
+        return Some((vec![0, 2], vec![1.0 - t, t]));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        let t = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return Some((vec![1, 2], vec![1.0 - t, t]));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+    use crate::{collision::algo, Aabb, Box2D, Circle, Convex, Line2D};
+
+    fn assert_separated(result: ClosestPoints, distance: f32, point1: Vec2, point2: Vec2) {
+        let ClosestPoints::Separated {
+            distance: d,
+            point1: p1,
+            point2: p2,
+        } = result
+        else {
+            panic!("expected the shapes to be separated, got {result:?}");
+        };
+        assert!((d - distance).abs() < 1e-4, "distance {d} != {distance}");
+        assert!(p1.abs_diff_eq(point1, 1e-4), "point1 {p1} != {point1}");
+        assert!(p2.abs_diff_eq(point2, 1e-4), "point2 {p2} != {point2}");
+    }
+
+    #[test]
+    fn distance_between_boxes() {
+        let b1 = Box2D::new(Vec2::ZERO, Vec2::splat(2.0), 0.0);
+        let b2 = Box2D::new(Vec2::new(5.0, 1.0), Vec2::new(7.0, 1.5), 0.0);
+        let result = closest_points(&b1, 0.0, &b2, 0.0);
+        assert_eq!(result.distance(), 3.0);
+
+        // Corner of a rotated box pointing at a face
+        let b2 = Box2D::new(Vec2::new(4.0, 0.0), Vec2::new(6.0, 2.0), PI / 4.0);
+        let corner_x = 5.0 - 2.0_f32.sqrt();
+        assert_separated(
+            closest_points(&b1, 0.0, &b2, 0.0),
+            corner_x - 2.0,
+            Vec2::new(2.0, 1.0),
+            Vec2::new(corner_x, 1.0),
+        );
+
+        let b2 = Box2D::new(Vec2::splat(1.0), Vec2::splat(3.0), PI / 4.0);
+        assert!(closest_points(&b1, 0.0, &b2, 0.0).is_overlapping());
+    }
+
+    #[test]
+    fn circles_use_their_radius() {
+        let c1 = Circle::new(Vec2::ZERO, 1.0);
+        let c2 = Circle::new(Vec2::new(0.0, 5.0), 2.0);
+        assert_separated(
+            closest_points(&c1.center, c1.radius, &c2.center, c2.radius),
+            2.0,
+            Vec2::new(0.0, 1.0),
+            Vec2::new(0.0, 3.0),
+        );
+
+        let b = Box2D::new(Vec2::new(-1.0, 3.5), Vec2::new(1.0, 5.0), 0.0);
+        assert_separated(
+            closest_points(&c1.center, c1.radius, &b, 0.0),
+            2.5,
+            Vec2::new(0.0, 1.0),
+            Vec2::new(0.0, 3.5),
+        );
+
+        let b = Box2D::new(Vec2::new(-1.0, 0.5), Vec2::new(1.0, 5.0), 0.0);
+        assert!(closest_points(&c1.center, c1.radius, &b, 0.0).is_overlapping());
+    }
+
+    #[test]
+    fn point_to_shape() {
+        let b = Box2D::new(Vec2::ZERO, Vec2::splat(2.0), 0.0);
+        let point = Vec2::new(4.0, 5.0);
+        assert_separated(
+            closest_points(&point, 0.0, &b, 0.0),
+            (4.0_f32 + 9.0).sqrt(),
+            point,
+            Vec2::splat(2.0),
+        );
+        assert!(closest_points(&Vec2::ONE, 0.0, &b.get_vertices(), 0.0).is_overlapping());
+    }
+
+    #[test]
+    fn conservative_advancement_cast() {
+        let circle = Circle::new(Vec2::ZERO, 1.0);
+        let b = Box2D::new(Vec2::new(4.0, -1.0), Vec2::new(6.0, 1.0), 0.0);
+        let hit = cast(&circle.center, circle.radius, Vec2::X, &b, 0.0, 10.0).unwrap();
+        assert!((hit.toi - 3.0).abs() < 1e-3);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-3));
+        assert!(hit.point.abs_diff_eq(Vec2::new(4.0, 0.0), 1e-3));
+
+        assert!(cast(&circle.center, circle.radius, Vec2::Y, &b, 0.0, 10.0).is_none());
+        assert!(cast(&circle.center, circle.radius, Vec2::X, &b, 0.0, 2.0).is_none());
+    }
+
+    #[test]
+    fn epa_matches_box_routine() {
+        let b1 = Box2D::new(Vec2::ZERO, Vec2::splat(2.0), 0.0);
+        for (min, rotation) in [
+            (Vec2::new(1.5, 0.5), 0.0),
+            (Vec2::new(0.5, 1.8), 0.0),
+            (Vec2::new(1.0, 0.0), 0.3),
+            (Vec2::new(-1.2, -0.4), PI / 4.0),
+        ] {
+            let b2 = Box2D::new(min, min + Vec2::new(2.0, 1.0), rotation);
+            let expected = algo::box2d_box2d(b1, b2).unwrap();
+            let manifold = contact(&b1, 0.0, &b2, 0.0).unwrap();
+            assert!(
+                manifold.normal.abs_diff_eq(expected.normal, 1e-4),
+                "normal {} != {}",
+                manifold.normal,
+                expected.normal
+            );
+            assert!((manifold.depth - expected.depth).abs() < 1e-4);
+            let gap = manifold.contact_point_a - manifold.contact_point_b;
+            assert!((gap.dot(manifold.normal) - manifold.depth).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn contact_with_radii() {
+        // Cores apart, only the radii overlap
+        let line = Line2D::new(Vec2::ZERO, Vec2::new(3.0, 0.0));
+        let center = Vec2::new(1.0, 1.5);
+        let manifold = contact(&center, 1.25, &line, 0.75).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::NEG_Y, 1e-5));
+        assert!((manifold.depth - 0.5).abs() < 1e-5);
+        assert!(manifold
+            .contact_point_a
+            .abs_diff_eq(Vec2::new(1.0, 0.25), 1e-5));
+        assert!(manifold
+            .contact_point_b
+            .abs_diff_eq(Vec2::new(1.0, 0.75), 1e-5));
+        assert!(contact(&center, 1.0, &line, 0.0).is_none());
+
+        // Crossing segments with a radius push apart the short way
+        let crossing = Line2D::new(Vec2::new(2.5, -0.2), Vec2::new(2.5, 2.0));
+        let manifold = contact(&line, 0.1, &crossing, 0.1).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-4));
+        assert!((manifold.depth - 0.4).abs() < 1e-4);
+    }
+
+    #[test]
+    fn contact_without_a_specialised_routine() {
+        // A triangle poking into the bottom of an AABB
+        let triangle = [Vec2::ZERO, Vec2::new(2.0, 0.0), Vec2::new(1.0, 2.0)];
+        let aabb = Aabb::new(Vec2::new(-1.0, 1.5), Vec2::new(3.0, 3.0));
+        let manifold = contact(&triangle, 0.25, &aabb, 0.0).unwrap();
+        assert!(manifold.normal.abs_diff_eq(Vec2::Y, 1e-4));
+        assert!((manifold.depth - 0.75).abs() < 1e-4);
+        assert!(manifold
+            .contact_point_a
+            .abs_diff_eq(Vec2::new(1.0, 2.25), 1e-4));
+        assert!(manifold
+            .contact_point_b
+            .abs_diff_eq(Vec2::new(1.0, 1.5), 1e-4));
+    }
+
+    #[test]
+    fn contact_between_coinciding_points() {
+        // EPA has no edge to take a normal from
+        assert!(contact(&Vec2::ONE, 0.0, &Vec2::ONE, 0.0).is_none());
+
+        let manifold = contact(&Vec2::ONE, 1.0, &Vec2::ONE, 0.5).unwrap();
+        assert!((manifold.normal.length() - 1.0).abs() < 1e-5);
+        assert!((manifold.depth - 1.5).abs() < 1e-5);
+        let gap = manifold.contact_point_a - manifold.contact_point_b;
+        assert!((gap.dot(manifold.normal) - manifold.depth).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,67 @@
+// Two colliders interact only if each one is a member of a group that is in
+// the filter of the other one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InteractionGroups {
+    pub memberships: u32,
+    pub filter: u32,
+}
+
+impl InteractionGroups {
+    pub const ALL: Self = Self::new(u32::MAX, u32::MAX);
+    pub const NONE: Self = Self::new(0, 0);
+
+    pub const fn new(memberships: u32, filter: u32) -> Self {
+        Self {
+            memberships,
+            filter,
+        }
+    }
+
+    pub const fn with_memberships(mut self, memberships: u32) -> Self {
+        self.memberships = memberships;
+        self
+    }
+
+    pub const fn with_filter(mut self, filter: u32) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub const fn test(self, other: Self) -> bool {
+        (self.memberships & other.filter) != 0 && (other.memberships & self.filter) != 0
+    }
+}
+
+impl Default for InteractionGroups {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: u32 = 1 << 0;
+    const PLAYER_BULLET: u32 = 1 << 1;
+    const ENEMY: u32 = 1 << 2;
+
+    #[test]
+    fn groups_interact_only_if_both_filters_match() {
+        let player = InteractionGroups::new(PLAYER, u32::MAX);
+        let bullet = InteractionGroups::new(PLAYER_BULLET, ENEMY);
+        let enemy = InteractionGroups::new(ENEMY, u32::MAX);
+
+        assert!(bullet.test(enemy));
+        assert!(enemy.test(bullet));
+        assert!(!bullet.test(bullet));
+        assert!(!bullet.test(player));
+        assert!(player.test(enemy));
+    }
+
+    #[test]
+    fn none_never_interacts() {
+        assert!(!InteractionGroups::NONE.test(InteractionGroups::ALL));
+        assert!(InteractionGroups::ALL.test(InteractionGroups::ALL));
+    }
+}
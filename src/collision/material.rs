@@ -0,0 +1,69 @@
+// How the coefficients of two colliders are combined. When the colliders use
+// different rules, the one that comes last in this list is used.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CoefficientCombineRule {
+    #[default]
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl CoefficientCombineRule {
+    pub fn combine(self, other: Self, coeff1: f32, coeff2: f32) -> f32 {
+        match self.max(other) {
+            CoefficientCombineRule::Average => (coeff1 + coeff2) / 2.0,
+            CoefficientCombineRule::Min => coeff1.min(coeff2),
+            CoefficientCombineRule::Multiply => coeff1 * coeff2,
+            CoefficientCombineRule::Max => coeff1.max(coeff2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColliderMaterial {
+    pub friction: f32,
+    // Coefficient of restitution
+    pub restitution: f32,
+    // Speed of the surface itself along the x axis of the collider, like a
+    // conveyor belt. It turns with the collider, and bodies touching it are
+    // dragged along by friction. Negative speeds run the other way.
+    pub tangent_speed: f32,
+    pub friction_combine_rule: CoefficientCombineRule,
+    pub restitution_combine_rule: CoefficientCombineRule,
+}
+
+impl ColliderMaterial {
+    pub const fn new(friction: f32, restitution: f32) -> Self {
+        Self {
+            friction,
+            restitution,
+            tangent_speed: 0.0,
+            friction_combine_rule: CoefficientCombineRule::Average,
+            restitution_combine_rule: CoefficientCombineRule::Min,
+        }
+    }
+
+    pub fn combined_friction(&self, other: &Self) -> f32 {
+        self.friction_combine_rule.combine(
+            other.friction_combine_rule,
+            self.friction,
+            other.friction,
+        )
+    }
+
+    pub fn combined_restitution(&self, other: &Self) -> f32 {
+        self.restitution_combine_rule.combine(
+            other.restitution_combine_rule,
+            self.restitution,
+            other.restitution,
+        )
+    }
+}
+
+// Frictionless and fully elastic, like contacts were before materials
+impl Default for ColliderMaterial {
+    fn default() -> Self {
+        Self::new(0.0, 1.0)
+    }
+}
@@ -1,6 +1,18 @@
 pub mod algo;
+mod broad_phase;
 mod collider;
 mod collider_set;
+mod event;
+pub mod gjk;
+mod interaction_groups;
+mod material;
 
+pub use broad_phase::*;
 pub use collider::*;
 pub use collider_set::*;
+pub use event::*;
+pub use interaction_groups::*;
+pub use material::*;
+
+#[cfg(test)]
+pub(crate) use broad_phase::test_utils;
@@ -0,0 +1,90 @@
+use std::{iter::Sum, ops::Add};
+
+use glam::Vec2;
+
+use crate::math;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MassProperties {
+    pub mass: f32,
+    // Center of mass relative to the body's position
+    pub local_center: Vec2,
+    // Moment of inertia around `local_center`
+    pub angular_inertia: f32,
+}
+
+impl MassProperties {
+    pub const fn new(mass: f32, local_center: Vec2, angular_inertia: f32) -> Self {
+        Self {
+            mass,
+            local_center,
+            angular_inertia,
+        }
+    }
+
+    pub fn inv_mass(&self) -> f32 {
+        math::recip_or_zero(self.mass)
+    }
+
+    pub fn inv_angular_inertia(&self) -> f32 {
+        math::recip_or_zero(self.angular_inertia)
+    }
+
+    // Parallel axis theorem
+    pub fn angular_inertia_about(&self, point: Vec2) -> f32 {
+        self.angular_inertia + self.mass * self.local_center.distance_squared(point)
+    }
+}
+
+impl Add for MassProperties {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let mass = self.mass + rhs.mass;
+        // Without mass there is no center to move, but inertia set by hand
+        // still adds up
+        if mass == 0.0 {
+            return Self {
+                mass,
+                local_center: self.local_center,
+                angular_inertia: self.angular_inertia + rhs.angular_inertia,
+            };
+        }
+
+        let local_center = (self.local_center * self.mass + rhs.local_center * rhs.mass) / mass;
+        let angular_inertia =
+            self.angular_inertia_about(local_center) + rhs.angular_inertia_about(local_center);
+        Self {
+            mass,
+            local_center,
+            angular_inertia,
+        }
+    }
+}
+
+impl Sum for MassProperties {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |acc, props| acc + props)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn massless_inertia_adds_up() {
+        let inertia = MassProperties::new(0.0, Vec2::ZERO, 2.0);
+        let sum = inertia + MassProperties::default();
+        assert_eq!(sum, inertia);
+        assert_relative_eq!((sum + inertia).angular_inertia, 4.0);
+
+        // Once there is mass, the inertia is kept about the new center
+        let mass = MassProperties::new(1.0, Vec2::new(3.0, 0.0), 0.5);
+        let sum = inertia + mass;
+        assert_eq!(sum.local_center, Vec2::new(3.0, 0.0));
+        assert_relative_eq!(sum.angular_inertia, 2.5);
+    }
+}
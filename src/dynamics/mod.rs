@@ -1,5 +1,7 @@
+mod mass_properties;
 mod rigid_body;
 mod rigid_body_set;
 
+pub use mass_properties::*;
 pub use rigid_body::*;
 pub use rigid_body_set::*;
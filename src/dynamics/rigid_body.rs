@@ -2,22 +2,31 @@ use glam::Vec2;
 
 use crate::{
     collision::{ColliderHandle, ColliderSet},
-    math::{self},
-    ForceRegistrationHandle,
+    ForceRegistrationHandle, MassProperties,
 };
 
 #[derive(Debug, Clone)]
 pub struct RigidBody {
     pub is_rotation_fixed: bool,
-    // Coefficient of restitution
+    // Coefficient of restitution. Contacts use the smallest of this and the
+    // restitution of the collider materials.
+    #[deprecated(note = "set `restitution` on the `material` of each collider")]
     pub cor: f32,
     pub(crate) position: Vec2,
     pub(crate) rotation: f32,
     pub(crate) mass: f32,
     pub(crate) inv_mass: f32,
+    pub(crate) local_center_of_mass: Vec2,
+    pub(crate) angular_inertia: f32,
+    pub(crate) inv_angular_inertia: f32,
+    // Mass set through `set_mass`, on top of the mass of the colliders
+    pub(crate) additional_mass_properties: MassProperties,
+    pub(crate) collider_mass_properties: MassProperties,
     pub(crate) force_accumulator: Vec2,
+    pub(crate) torque_accumulator: f32,
     pub(crate) linear_velocity: Vec2,
-    pub(crate) collider: Option<ColliderHandle>,
+    pub(crate) angular_velocity: f32,
+    pub(crate) colliders: Vec<ColliderHandle>,
     pub(crate) force_registrations: Vec<ForceRegistrationHandle>,
 }
 
@@ -27,48 +36,131 @@ impl RigidBody {
             position,
             rotation,
             is_rotation_fixed: false,
+            #[allow(deprecated)]
             cor: 1.0,
             mass: 0.0,
             inv_mass: 0.0,
+            local_center_of_mass: Vec2::ZERO,
+            angular_inertia: 0.0,
+            inv_angular_inertia: 0.0,
+            additional_mass_properties: MassProperties::default(),
+            collider_mass_properties: MassProperties::default(),
             force_accumulator: Vec2::ZERO,
+            torque_accumulator: 0.0,
             linear_velocity: Vec2::ZERO,
-            collider: None,
+            angular_velocity: 0.0,
+            colliders: Vec::new(),
             force_registrations: Vec::new(),
         }
     }
 
+    // Bodies with infinite mass ignore forces, but still move at the velocity
+    // they were given. That makes them kinematic.
     pub fn physics_update(&mut self, dt: f32, colliders: &mut ColliderSet) {
-        if self.mass == 0.0 {
-            return;
+        self.integrate_forces(dt);
+        self.integrate_velocity(dt, colliders);
+    }
+
+    // Turns the accumulated forces into velocity
+    pub(crate) fn integrate_forces(&mut self, dt: f32) {
+        if !self.has_infinite_mass() {
+            let acceleration = self.force_accumulator * self.inv_mass;
+            self.linear_velocity += acceleration * dt;
+            if !self.is_rotation_fixed {
+                let angular_acceleration = self.torque_accumulator * self.inv_angular_inertia;
+                self.angular_velocity += angular_acceleration * dt;
+            }
         }
+        self.clear_accumelators();
+    }
 
-        let acceleration = self.force_accumulator * self.inv_mass;
-        self.linear_velocity += acceleration * dt;
-        self.position += self.linear_velocity * dt;
+    pub(crate) fn integrate_velocity(&mut self, dt: f32, colliders: &mut ColliderSet) {
+        if self.linear_velocity == Vec2::ZERO && self.angular_velocity == 0.0 {
+            return;
+        }
 
-        if let Some(collider) = self.collider {
-            colliders[collider].update_position(self.position);
+        // The body moves and rotates around its center of mass
+        let center_of_mass = self.center_of_mass() + self.linear_velocity * dt;
+        if !self.is_rotation_fixed {
+            self.rotation += self.angular_velocity * dt;
         }
+        self.position =
+            center_of_mass - Vec2::from_angle(self.rotation).rotate(self.local_center_of_mass);
 
-        self.clear_accumelators()
+        self.sync_colliders(colliders);
+    }
+
+    pub(crate) fn sync_colliders(&self, colliders: &mut ColliderSet) {
+        for collider in self.colliders.iter().copied() {
+            colliders[collider].update_transform(self.position, self.rotation);
+        }
     }
 
     pub fn add_force(&mut self, force: Vec2) {
         self.force_accumulator += force;
     }
 
-    pub fn set_collider(&mut self, collider: ColliderHandle) {
-        self.collider = Some(collider);
+    pub fn apply_force_at_point(&mut self, force: Vec2, point: Vec2) {
+        self.force_accumulator += force;
+        self.torque_accumulator += (point - self.center_of_mass()).perp_dot(force);
+    }
+
+    pub fn apply_torque(&mut self, torque: f32) {
+        self.torque_accumulator += torque;
+    }
+
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.linear_velocity += impulse * self.inv_mass;
+    }
+
+    pub fn apply_impulse_at_point(&mut self, impulse: Vec2, point: Vec2) {
+        self.apply_impulse(impulse);
+        let torque_impulse = (point - self.center_of_mass()).perp_dot(impulse);
+        self.angular_velocity += torque_impulse * self.effective_inv_angular_inertia();
+    }
+
+    // Velocity of the point of the body that is at `point` in world space
+    pub fn velocity_at_point(&self, point: Vec2) -> Vec2 {
+        self.linear_velocity + self.angular_velocity * (point - self.center_of_mass()).perp()
+    }
+
+    // How easily an impulse along `direction` at `point` changes the velocity
+    // of the body at that point, in the same direction
+    pub(crate) fn inv_mass_at_point(&self, point: Vec2, direction: Vec2) -> f32 {
+        let arm = (point - self.center_of_mass()).perp_dot(direction);
+        self.inv_mass + self.effective_inv_angular_inertia() * arm * arm
+    }
+
+    // Zero when contacts and impulses must not turn the body
+    fn effective_inv_angular_inertia(&self) -> f32 {
+        if self.is_rotation_fixed || self.has_infinite_mass() {
+            0.0
+        } else {
+            self.inv_angular_inertia
+        }
     }
 
     pub fn clear_accumelators(&mut self) {
         self.force_accumulator = Vec2::ZERO;
+        self.torque_accumulator = 0.0;
     }
 
     pub fn linear_velocity(&self) -> Vec2 {
         self.linear_velocity
     }
 
+    pub fn set_linear_velocity(&mut self, linear_velocity: Vec2) {
+        self.linear_velocity = linear_velocity;
+    }
+
+    pub fn angular_velocity(&self) -> f32 {
+        self.angular_velocity
+    }
+
+    pub fn set_angular_velocity(&mut self, angular_velocity: f32) {
+        self.angular_velocity = angular_velocity;
+    }
+
     pub fn mass(&self) -> f32 {
         self.mass
     }
@@ -78,23 +170,255 @@ impl RigidBody {
     }
 
     pub fn set_mass(&mut self, mass: f32) {
-        self.mass = mass;
-        self.inv_mass = math::recip_or_zero(mass);
+        self.additional_mass_properties.mass = mass;
+        self.update_mass_properties();
+    }
+
+    pub fn set_angular_inertia(&mut self, angular_inertia: f32) {
+        self.additional_mass_properties.angular_inertia = angular_inertia;
+        self.update_mass_properties();
     }
 
     pub fn has_infinite_mass(&self) -> bool {
         self.mass == 0.0
     }
 
+    pub fn local_center_of_mass(&self) -> Vec2 {
+        self.local_center_of_mass
+    }
+
+    pub fn center_of_mass(&self) -> Vec2 {
+        self.position + Vec2::from_angle(self.rotation).rotate(self.local_center_of_mass)
+    }
+
+    pub fn angular_inertia(&self) -> f32 {
+        self.angular_inertia
+    }
+
+    pub fn inv_angular_inertia(&self) -> f32 {
+        self.inv_angular_inertia
+    }
+
+    pub fn mass_properties(&self) -> MassProperties {
+        MassProperties::new(self.mass, self.local_center_of_mass, self.angular_inertia)
+    }
+
+    pub fn recompute_mass_properties(&mut self, colliders: &ColliderSet) {
+        self.collider_mass_properties = self
+            .colliders
+            .iter()
+            .filter_map(|handle| colliders.get(*handle))
+            .map(|collider| collider.mass_properties())
+            .sum();
+        self.update_mass_properties();
+    }
+
+    fn update_mass_properties(&mut self) {
+        let props = self.additional_mass_properties + self.collider_mass_properties;
+        self.mass = props.mass;
+        self.inv_mass = props.inv_mass();
+        self.local_center_of_mass = props.local_center;
+        self.angular_inertia = props.angular_inertia;
+        self.inv_angular_inertia = props.inv_angular_inertia();
+    }
+
     pub fn position(&self) -> Vec2 {
         self.position
     }
 
+    pub fn set_position(&mut self, position: Vec2, colliders: &mut ColliderSet) {
+        self.position = position;
+        self.sync_colliders(colliders);
+    }
+
     pub fn rotation(&self) -> f32 {
         self.rotation
     }
 
+    pub fn set_rotation(&mut self, rotation: f32, colliders: &mut ColliderSet) {
+        self.rotation = rotation;
+        self.sync_colliders(colliders);
+    }
+
+    pub fn colliders(&self) -> &[ColliderHandle] {
+        &self.colliders
+    }
+
+    #[allow(deprecated)]
+    pub(crate) fn restitution(&self) -> f32 {
+        self.cor
+    }
+
+    // Bodies used to have a single collider. This only records the handle, it
+    // doesn't set the parent of the collider or update the mass.
+    #[deprecated(note = "use `ColliderSet::insert_with_parent` to attach colliders")]
+    pub fn set_collider(&mut self, collider: ColliderHandle) {
+        if !self.colliders.contains(&collider) {
+            self.colliders.push(collider);
+        }
+    }
+
+    #[deprecated(note = "bodies can have several colliders, use `colliders`")]
     pub fn collider(&self) -> Option<ColliderHandle> {
-        self.collider
+        self.colliders.first().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::{
+        collision::{Collider, ColliderShape},
+        Box2D, RigidBodySet,
+    };
+
+    fn box_collider(size: Vec2, local_position: Vec2) -> Collider {
+        let mut collider = Collider::with_local_transform(
+            ColliderShape::Box2D(Box2D::new(Vec2::ZERO, size, 0.0)),
+            local_position,
+            0.0,
+        );
+        collider.density = 1.0;
+        collider
+    }
+
+    #[test]
+    fn compound_mass_uses_parallel_axis() {
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let handle = bodies.insert(RigidBody::new(Vec2::new(5.0, 5.0), 0.0));
+        let size = Vec2::new(2.0, 1.0);
+        for local_position in [Vec2::new(-2.0, 0.0), Vec2::new(2.0, 0.0)] {
+            colliders.insert_with_parent(box_collider(size, local_position), handle, &mut bodies);
+        }
+
+        let body = &bodies[handle];
+        assert_relative_eq!(body.mass(), 4.0);
+        assert!(body.local_center_of_mass().abs_diff_eq(Vec2::ZERO, 1e-6));
+        // Each box spins around its own center and is 2 away from the body's
+        let box_inertia = 2.0 * size.length_squared() / 12.0;
+        assert_relative_eq!(body.angular_inertia(), 2.0 * (box_inertia + 2.0 * 4.0));
+        assert_relative_eq!(body.inv_angular_inertia(), body.angular_inertia().recip());
+    }
+
+    #[test]
+    fn mass_follows_colliders() {
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let handle = bodies.insert(RigidBody::new(Vec2::ZERO, 0.0));
+        let small = box_collider(Vec2::ONE, Vec2::ZERO);
+        let small = colliders.insert_with_parent(small, handle, &mut bodies);
+        let big = box_collider(Vec2::splat(2.0), Vec2::new(3.0, 0.0));
+        colliders.insert_with_parent(big, handle, &mut bodies);
+
+        let body = &bodies[handle];
+        assert_relative_eq!(body.mass(), 5.0);
+        assert!(body
+            .local_center_of_mass()
+            .abs_diff_eq(Vec2::new(2.4, 0.0), 1e-6));
+
+        // Mass set by hand adds to the colliders, at the body's position
+        bodies[handle].set_mass(3.0);
+        assert_relative_eq!(bodies[handle].mass(), 8.0);
+        assert!(bodies[handle]
+            .local_center_of_mass()
+            .abs_diff_eq(Vec2::new(1.5, 0.0), 1e-6));
+
+        colliders.remove(small, &mut bodies);
+        let body = &mut bodies[handle];
+        assert_relative_eq!(body.mass(), 7.0);
+        body.set_mass(0.0);
+        body.recompute_mass_properties(&colliders);
+        assert_relative_eq!(body.mass(), 4.0);
+        assert!(body
+            .local_center_of_mass()
+            .abs_diff_eq(Vec2::new(3.0, 0.0), 1e-6));
+        assert_relative_eq!(body.angular_inertia(), 4.0 * 8.0 / 12.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn setters_move_colliders_right_away() {
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let handle = bodies.insert(RigidBody::new(Vec2::ZERO, 0.0));
+        let collider = box_collider(Vec2::ONE, Vec2::new(2.0, 0.0));
+        let collider = colliders.insert_with_parent(collider, handle, &mut bodies);
+
+        bodies[handle].set_position(Vec2::new(1.0, 1.0), &mut colliders);
+        assert!(colliders[collider]
+            .shape
+            .center()
+            .abs_diff_eq(Vec2::new(3.0, 1.0), 1e-6));
+        bodies[handle].set_rotation(std::f32::consts::FRAC_PI_2, &mut colliders);
+        assert!(colliders[collider]
+            .shape
+            .center()
+            .abs_diff_eq(Vec2::new(1.0, 3.0), 1e-5));
+    }
+
+    #[test]
+    fn impulses_and_forces_at_points_turn_the_body() {
+        let mut colliders = ColliderSet::new();
+        let mut body = RigidBody::new(Vec2::ZERO, 0.0);
+        body.set_mass(2.0);
+        body.set_angular_inertia(4.0);
+
+        body.apply_impulse(Vec2::new(2.0, 0.0));
+        assert_eq!(body.linear_velocity(), Vec2::new(1.0, 0.0));
+        body.apply_impulse_at_point(Vec2::new(0.0, 2.0), Vec2::new(2.0, 0.0));
+        assert_eq!(body.linear_velocity(), Vec2::new(1.0, 1.0));
+        assert_relative_eq!(body.angular_velocity(), 1.0);
+        assert!(body
+            .velocity_at_point(Vec2::new(2.0, 0.0))
+            .abs_diff_eq(Vec2::new(1.0, 3.0), 1e-6));
+
+        body.set_linear_velocity(Vec2::ZERO);
+        body.set_angular_velocity(0.0);
+        // A push at the side and a torque that cancels its turning
+        body.apply_force_at_point(Vec2::new(0.0, 4.0), Vec2::new(1.0, 0.0));
+        body.apply_torque(-4.0);
+        body.physics_update(0.5, &mut colliders);
+        assert!(body
+            .linear_velocity()
+            .abs_diff_eq(Vec2::new(0.0, 1.0), 1e-6));
+        assert_eq!(body.angular_velocity(), 0.0);
+
+        body.apply_torque(4.0);
+        body.physics_update(0.5, &mut colliders);
+        assert_relative_eq!(body.angular_velocity(), 0.5);
+        assert_relative_eq!(body.rotation(), 0.25);
+
+        body.is_rotation_fixed = true;
+        body.set_angular_velocity(0.0);
+        body.apply_impulse_at_point(Vec2::new(0.0, 2.0), Vec2::new(2.0, 0.0));
+        assert_eq!(body.angular_velocity(), 0.0);
+    }
+
+    #[test]
+    fn bodies_without_mass_move_at_their_velocity() {
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let handle = bodies.insert(RigidBody::new(Vec2::ZERO, 0.0));
+        let collider = Collider {
+            density: 0.0,
+            ..box_collider(Vec2::ONE, Vec2::ZERO)
+        };
+        let collider = colliders.insert_with_parent(collider, handle, &mut bodies);
+
+        let body = &mut bodies[handle];
+        assert!(body.has_infinite_mass());
+        body.set_linear_velocity(Vec2::new(2.0, 0.0));
+        body.set_angular_velocity(1.0);
+        body.add_force(Vec2::new(0.0, 100.0));
+        body.apply_impulse(Vec2::new(0.0, 100.0));
+        body.physics_update(0.5, &mut colliders);
+        assert_eq!(body.position(), Vec2::new(1.0, 0.0));
+        assert_relative_eq!(body.rotation(), 0.5);
+        assert!(colliders[collider]
+            .shape
+            .center()
+            .abs_diff_eq(Vec2::new(1.0, 0.0), 1e-6));
     }
 }
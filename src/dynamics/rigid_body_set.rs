@@ -25,7 +25,9 @@ impl RigidBodySet {
     ) -> Option<RigidBody> {
         let body = self.bodies.remove(handle.0)?;
 
-        if let Some(collider) = body.collider {
+        // The colliders stay where they are and act as static geometry from
+        // now on. Remove them from `colliders` as well if that's not wanted.
+        for collider in body.colliders.iter().copied() {
             colliders[collider].parent = None;
         }
 
@@ -69,6 +71,19 @@ impl RigidBodySet {
     ) -> (Option<&mut RigidBody>, Option<&mut RigidBody>) {
         self.bodies.get2_mut(handle1.0, handle2.0)
     }
+
+    pub(crate) fn get_pair_mut(
+        &mut self,
+        handle1: Option<RigidBodyHandle>,
+        handle2: Option<RigidBodyHandle>,
+    ) -> (Option<&mut RigidBody>, Option<&mut RigidBody>) {
+        match (handle1, handle2) {
+            (Some(handle1), Some(handle2)) => self.get2_mut(handle1, handle2),
+            (Some(handle), None) => (self.get_mut(handle), None),
+            (None, Some(handle)) => (None, self.get_mut(handle)),
+            (None, None) => (None, None),
+        }
+    }
 }
 
 impl std::ops::Index<RigidBodyHandle> for RigidBodySet {
@@ -85,5 +100,5 @@ impl std::ops::IndexMut<RigidBodyHandle> for RigidBodySet {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct RigidBodyHandle(pub Index);
@@ -0,0 +1,94 @@
+use std::f32::consts::PI;
+
+use glam::Vec2;
+
+use super::Line2D;
+
+// All points within `radius` of the segment from `a` to `b`. The segment is
+// relative to `position` and unrotated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capsule {
+    pub a: Vec2,
+    pub b: Vec2,
+    pub radius: f32,
+    pub position: Vec2,
+    pub rotation: f32,
+}
+
+impl Capsule {
+    pub fn new(a: Vec2, b: Vec2, radius: f32) -> Self {
+        Self {
+            a,
+            b,
+            radius,
+            position: Vec2::ZERO,
+            rotation: 0.0,
+        }
+    }
+
+    // Standing upright, `half_height` is the distance from the center to the
+    // center of each cap
+    pub fn vertical(half_height: f32, radius: f32) -> Self {
+        Self::new(
+            Vec2::new(0.0, -half_height),
+            Vec2::new(0.0, half_height),
+            radius,
+        )
+    }
+
+    // The inner segment in world space
+    pub fn segment(&self) -> Line2D {
+        let rotation_vec = Vec2::from_angle(self.rotation);
+        Line2D::new(
+            self.position + rotation_vec.rotate(self.a),
+            self.position + rotation_vec.rotate(self.b),
+        )
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.position + Vec2::from_angle(self.rotation).rotate((self.a + self.b) / 2.0)
+    }
+
+    // Area, centroid relative to `position` and unrotated, and moment of
+    // inertia about the centroid for a density of 1
+    pub fn area_centroid_and_inertia(&self) -> (f32, Vec2, f32) {
+        let length = self.a.distance(self.b);
+        let radius_squared = self.radius * self.radius;
+        let box_area = length * 2.0 * self.radius;
+        let circle_area = PI * radius_squared;
+        let box_inertia = box_area * (4.0 * radius_squared + length * length) / 12.0;
+        // The caps are two half circles, each with its center of mass moved
+        // out from the end of the segment
+        let half_length = length / 2.0;
+        let cap_offset = 4.0 * self.radius / (3.0 * PI);
+        let circle_inertia = circle_area
+            * (radius_squared / 2.0 + half_length * half_length + 2.0 * half_length * cap_offset);
+        (
+            box_area + circle_area,
+            (self.a + self.b) / 2.0,
+            box_inertia + circle_inertia,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn short_capsule_has_circle_mass() {
+        let (area, centroid, inertia) = Capsule::vertical(0.0, 2.0).area_centroid_and_inertia();
+        assert_relative_eq!(area, PI * 4.0);
+        assert_eq!(centroid, Vec2::ZERO);
+        assert_relative_eq!(inertia, area * 4.0 / 2.0);
+
+        let mut capsule = Capsule::new(Vec2::ZERO, Vec2::new(2.0, 0.0), 0.5);
+        capsule.position = Vec2::new(1.0, 1.0);
+        capsule.rotation = std::f32::consts::FRAC_PI_2;
+        let segment = capsule.segment();
+        assert!(segment.end.abs_diff_eq(Vec2::new(1.0, 3.0), 1e-6));
+        assert!(capsule.center().abs_diff_eq(Vec2::new(1.0, 2.0), 1e-6));
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt;
+
+use glam::Vec2;
+
+use super::{Box2D, Convex};
+
+// Raise this to allow polygons with more vertices. Every polygon takes up
+// room for this many.
+pub const MAX_POLYGON_VERTICES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonError {
+    TooFewVertices,
+    TooManyVertices,
+    // Two vertices are on top of each other, or three are on a line
+    Degenerate,
+    NotConvex,
+    // The vertices have to go around the polygon counter-clockwise
+    ClockwiseWinding,
+    // Two edges of the polygon cross or touch
+    SelfIntersecting,
+    // A mesh without any triangles
+    EmptyMesh,
+    // A triangle of a mesh uses a vertex that doesn't exist
+    VertexIndexOutOfBounds,
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolygonError::TooFewVertices => write!(f, "a polygon needs at least 3 vertices"),
+            PolygonError::TooManyVertices => {
+                write!(f, "a polygon has at most {MAX_POLYGON_VERTICES} vertices")
+            }
+            PolygonError::Degenerate => write!(f, "the polygon has no area at some vertex"),
+            PolygonError::NotConvex => write!(f, "the polygon is not convex"),
+            PolygonError::ClockwiseWinding => {
+                write!(f, "the polygon vertices are in clockwise order")
+            }
+            PolygonError::SelfIntersecting => write!(f, "the polygon edges cross each other"),
+            PolygonError::EmptyMesh => write!(f, "a mesh needs at least 1 triangle"),
+            PolygonError::VertexIndexOutOfBounds => {
+                write!(
+                    f,
+                    "a triangle uses a vertex index past the end of the vertices"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolygonError {}
+
+// Vertices and edge normals are stored relative to `position` and unrotated.
+// Edge `i` goes from vertex `i` to vertex `i + 1`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConvexPolygon {
+    vertices: [Vec2; MAX_POLYGON_VERTICES],
+    normals: [Vec2; MAX_POLYGON_VERTICES],
+    len: usize,
+    pub position: Vec2,
+    pub rotation: f32,
+}
+
+impl ConvexPolygon {
+    pub fn new(vertices: &[Vec2]) -> Result<Self, PolygonError> {
+        if vertices.len() < 3 {
+            return Err(PolygonError::TooFewVertices);
+        }
+        if vertices.len() > MAX_POLYGON_VERTICES {
+            return Err(PolygonError::TooManyVertices);
+        }
+
+        // Every corner has to turn the same way, and all of them left. The
+        // tolerance for straight corners scales with their edges, so it
+        // doesn't depend on the size of the polygon.
+        let turns = vertices.iter().enumerate().map(|(i, vertex)| {
+            let previous = vertices[(i + vertices.len() - 1) % vertices.len()];
+            let next = vertices[(i + 1) % vertices.len()];
+            let (edge1, edge2) = (*vertex - previous, next - *vertex);
+            let tolerance = f32::EPSILON * edge1.length() * edge2.length();
+            (edge1.perp_dot(edge2), tolerance)
+        });
+        let mut has_left_turn = false;
+        let mut has_right_turn = false;
+        for (turn, tolerance) in turns {
+            if turn.abs() <= tolerance {
+                return Err(PolygonError::Degenerate);
+            }
+            has_left_turn |= turn > 0.0;
+            has_right_turn |= turn < 0.0;
+        }
+        if has_left_turn && has_right_turn {
+            return Err(PolygonError::NotConvex);
+        }
+        if has_right_turn {
+            return Err(PolygonError::ClockwiseWinding);
+        }
+
+        // A star shape turns left at every corner but goes around more than once
+        let angle_sum: f32 = (0..vertices.len())
+            .map(|i| {
+                let edge = vertices[(i + 1) % vertices.len()] - vertices[i];
+                let next_edge =
+                    vertices[(i + 2) % vertices.len()] - vertices[(i + 1) % vertices.len()];
+                edge.angle_between(next_edge)
+            })
+            .sum();
+        if angle_sum > std::f32::consts::TAU + 1e-3 {
+            return Err(PolygonError::NotConvex);
+        }
+
+        Ok(Self::from_valid_vertices(vertices))
+    }
+
+    fn from_valid_vertices(vertices: &[Vec2]) -> Self {
+        let mut polygon = Self {
+            vertices: [Vec2::ZERO; MAX_POLYGON_VERTICES],
+            normals: [Vec2::ZERO; MAX_POLYGON_VERTICES],
+            len: vertices.len(),
+            position: Vec2::ZERO,
+            rotation: 0.0,
+        };
+        for (i, vertex) in vertices.iter().enumerate() {
+            let edge = vertices[(i + 1) % vertices.len()] - *vertex;
+            polygon.vertices[i] = *vertex;
+            // Points to the right of the edge, which is outside for
+            // counter-clockwise polygons
+            polygon.normals[i] = Vec2::new(edge.y, -edge.x).normalize_or_zero();
+        }
+        polygon
+    }
+
+    // Regular polygon with its vertices `radius` away from the origin
+    pub fn regular(sides: usize, radius: f32) -> Result<Self, PolygonError> {
+        let vertices: Vec<_> = (0..sides)
+            .map(|i| Vec2::from_angle(i as f32 * std::f32::consts::TAU / sides as f32) * radius)
+            .collect();
+        Self::new(&vertices)
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.len
+    }
+
+    pub fn local_vertices(&self) -> &[Vec2] {
+        &self.vertices[..self.len]
+    }
+
+    pub fn local_normals(&self) -> &[Vec2] {
+        &self.normals[..self.len]
+    }
+
+    pub fn vertex(&self, i: usize) -> Vec2 {
+        self.position + Vec2::from_angle(self.rotation).rotate(self.vertices[i])
+    }
+
+    // Outward normal of the edge starting at vertex `i`
+    pub fn normal(&self, i: usize) -> Vec2 {
+        Vec2::from_angle(self.rotation).rotate(self.normals[i])
+    }
+
+    pub fn vertices(&self) -> impl Iterator<Item = Vec2> + '_ {
+        (0..self.len).map(|i| self.vertex(i))
+    }
+
+    // Area, centroid relative to `position` and unrotated, and moment of
+    // inertia about the centroid for a density of 1
+    pub fn area_centroid_and_inertia(&self) -> (f32, Vec2, f32) {
+        // Sum over the triangles between the first vertex and each edge
+        let origin = self.vertices[0];
+        let mut area = 0.0;
+        let mut centroid = Vec2::ZERO;
+        let mut inertia = 0.0;
+        for i in 1..self.len - 1 {
+            let e1 = self.vertices[i] - origin;
+            let e2 = self.vertices[i + 1] - origin;
+            let triangle_area = e1.perp_dot(e2) / 2.0;
+            area += triangle_area;
+            centroid += triangle_area * (e1 + e2) / 3.0;
+            let intx2 = e1.x * e1.x + e2.x * e1.x + e2.x * e2.x;
+            let inty2 = e1.y * e1.y + e2.y * e1.y + e2.y * e2.y;
+            inertia += triangle_area / 6.0 * (intx2 + inty2);
+        }
+
+        centroid /= area;
+        // Move the inertia from `origin` to the centroid
+        inertia -= area * centroid.length_squared();
+        (area, origin + centroid, inertia)
+    }
+
+    pub fn centroid(&self) -> Vec2 {
+        let (_, centroid, _) = self.area_centroid_and_inertia();
+        self.position + Vec2::from_angle(self.rotation).rotate(centroid)
+    }
+}
+
+impl From<Box2D> for ConvexPolygon {
+    fn from(b: Box2D) -> Self {
+        let half_size = b.half_size();
+        let mut polygon = Self::from_valid_vertices(&[
+            Vec2::new(-half_size.x, -half_size.y),
+            Vec2::new(half_size.x, -half_size.y),
+            Vec2::new(half_size.x, half_size.y),
+            Vec2::new(-half_size.x, half_size.y),
+        ]);
+        polygon.position = b.center();
+        polygon.rotation = b.rotation;
+        polygon
+    }
+}
+
+// Unused slots repeat the first vertex, which changes nothing for code that
+// only looks at the extent of the shape
+impl Convex<MAX_POLYGON_VERTICES> for ConvexPolygon {
+    fn get_vertices(&self) -> [Vec2; MAX_POLYGON_VERTICES] {
+        let mut vertices = [self.vertex(0); MAX_POLYGON_VERTICES];
+        for (i, vertex) in vertices.iter_mut().enumerate().take(self.len).skip(1) {
+            *vertex = self.vertex(i);
+        }
+        vertices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn validates_vertices() {
+        let triangle = [Vec2::ZERO, Vec2::X, Vec2::Y];
+        assert!(ConvexPolygon::new(&triangle).is_ok());
+        assert_eq!(
+            ConvexPolygon::new(&[Vec2::ZERO, Vec2::Y, Vec2::X]),
+            Err(PolygonError::ClockwiseWinding)
+        );
+        assert_eq!(
+            ConvexPolygon::new(&[Vec2::ZERO, Vec2::X]),
+            Err(PolygonError::TooFewVertices)
+        );
+        assert_eq!(
+            ConvexPolygon::new(&[Vec2::ZERO, Vec2::X, Vec2::new(2.0, 0.0), Vec2::Y]),
+            Err(PolygonError::Degenerate)
+        );
+        let arrow = [
+            Vec2::ZERO,
+            Vec2::new(2.0, 1.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(0.5, 1.0),
+        ];
+        assert_eq!(ConvexPolygon::new(&arrow), Err(PolygonError::NotConvex));
+        let star: Vec<_> = (0..5)
+            .map(|i| Vec2::from_angle(i as f32 * 2.0 * std::f32::consts::TAU / 5.0))
+            .collect();
+        assert_eq!(ConvexPolygon::new(&star), Err(PolygonError::NotConvex));
+        assert_eq!(
+            ConvexPolygon::regular(MAX_POLYGON_VERTICES + 1, 1.0),
+            Err(PolygonError::TooManyVertices)
+        );
+    }
+
+    #[test]
+    fn straightness_scales_with_the_polygon() {
+        // A square a tenth of a millimetre across
+        let small = [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y].map(|v| v * 1e-4);
+        assert!(ConvexPolygon::new(&small).is_ok());
+
+        // Two kilometre long edges that bend by a hundredth of a millimetre
+        let nearly_straight = [
+            Vec2::ZERO,
+            Vec2::new(1000.0, -1e-5),
+            Vec2::new(2000.0, 0.0),
+            Vec2::new(1000.0, 1000.0),
+        ];
+        assert_eq!(
+            ConvexPolygon::new(&nearly_straight),
+            Err(PolygonError::Degenerate)
+        );
+    }
+
+    #[test]
+    fn mass_matches_box() {
+        let b = Box2D::new(Vec2::ZERO, Vec2::new(4.0, 2.0), 0.0);
+        let mut polygon = ConvexPolygon::new(&[
+            Vec2::new(1.0, 1.0),
+            Vec2::new(5.0, 1.0),
+            Vec2::new(5.0, 3.0),
+            Vec2::new(1.0, 3.0),
+        ])
+        .unwrap();
+        let (area, centroid, inertia) = polygon.area_centroid_and_inertia();
+        let size = b.size();
+        assert_relative_eq!(area, 8.0, epsilon = 1e-5);
+        assert!(centroid.abs_diff_eq(Vec2::new(3.0, 2.0), 1e-5));
+        assert_relative_eq!(inertia, area * size.length_squared() / 12.0, epsilon = 1e-4);
+
+        polygon.position = Vec2::new(1.0, 0.0);
+        polygon.rotation = std::f32::consts::FRAC_PI_2;
+        assert!(polygon.centroid().abs_diff_eq(Vec2::new(-1.0, 3.0), 1e-5));
+        assert!(polygon.normal(0).abs_diff_eq(Vec2::X, 1e-6));
+    }
+}
@@ -0,0 +1,264 @@
+use glam::Vec2;
+
+use super::{ConvexPolygon, PolygonError, MAX_POLYGON_VERTICES};
+
+// Splits a simple polygon into triangles by ear clipping. The triangles are
+// indices into `vertices`, counter-clockwise. The polygon may wind either way,
+// and vertices on a line with their neighbours are left out.
+pub fn triangulate(vertices: &[Vec2]) -> Result<Vec<[usize; 3]>, PolygonError> {
+    if vertices.len() < 3 {
+        return Err(PolygonError::TooFewVertices);
+    }
+
+    let mut remaining = outline(vertices)?;
+    let mut triangles = Vec::with_capacity(remaining.len() - 2);
+    while remaining.len() > 3 {
+        let ear = (0..remaining.len())
+            .find(|i| is_ear(vertices, &remaining, *i))
+            .ok_or(PolygonError::SelfIntersecting)?;
+        let len = remaining.len();
+        triangles.push([
+            remaining[(ear + len - 1) % len],
+            remaining[ear],
+            remaining[(ear + 1) % len],
+        ]);
+        remaining.remove(ear);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    Ok(triangles)
+}
+
+// Splits a simple polygon into convex pieces, for example to build a compound
+// collider. The triangles from `triangulate` are merged for as long as the
+// result stays convex (Hertel-Mehlhorn), which gives at most four times the
+// fewest pieces possible.
+pub fn convex_decomposition(vertices: &[Vec2]) -> Result<Vec<ConvexPolygon>, PolygonError> {
+    let mut pieces: Vec<Vec<usize>> = triangulate(vertices)?.into_iter().map(Vec::from).collect();
+
+    let mut merged_any = true;
+    while merged_any {
+        merged_any = false;
+        'search: for i in 0..pieces.len() {
+            for j in i + 1..pieces.len() {
+                if let Some(merged) = merge(vertices, &pieces[i], &pieces[j]) {
+                    pieces[i] = merged;
+                    pieces.swap_remove(j);
+                    merged_any = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    pieces
+        .iter()
+        .map(|piece| {
+            let points: Vec<_> = piece.iter().map(|i| vertices[*i]).collect();
+            ConvexPolygon::new(&points)
+        })
+        .collect()
+}
+
+// Indices of the corners of the polygon in counter-clockwise order, after
+// checking that its edges don't cross
+fn outline(vertices: &[Vec2]) -> Result<Vec<usize>, PolygonError> {
+    let mut indices: Vec<usize> = (0..vertices.len()).collect();
+    let mut removed_any = true;
+    while removed_any && indices.len() >= 3 {
+        removed_any = false;
+        for i in 0..indices.len() {
+            let len = indices.len();
+            let previous = vertices[indices[(i + len - 1) % len]];
+            let next = vertices[indices[(i + 1) % len]];
+            let corner = vertices[indices[i]];
+            if turn(previous, corner, next).abs() <= straight_tolerance(previous, corner, next) {
+                indices.remove(i);
+                removed_any = true;
+                break;
+            }
+        }
+    }
+    if indices.len() < 3 {
+        return Err(PolygonError::Degenerate);
+    }
+
+    let len = indices.len();
+    let edge = |i: usize| (vertices[indices[i]], vertices[indices[(i + 1) % len]]);
+    for i in 0..len {
+        // Skip the edge itself and its neighbours, which share a vertex
+        for j in i + 2..len {
+            if i == 0 && j == len - 1 {
+                continue;
+            }
+            let (a1, a2) = edge(i);
+            let (b1, b2) = edge(j);
+            if segments_touch(a1, a2, b1, b2) {
+                return Err(PolygonError::SelfIntersecting);
+            }
+        }
+    }
+
+    let signed_area: f32 = (0..len)
+        .map(|i| {
+            let (start, end) = edge(i);
+            start.perp_dot(end)
+        })
+        .sum();
+    if signed_area < 0.0 {
+        indices.reverse();
+    }
+    Ok(indices)
+}
+
+// A corner is an ear when it is convex and no other corner is inside the
+// triangle it makes with its neighbours
+fn is_ear(vertices: &[Vec2], remaining: &[usize], i: usize) -> bool {
+    let len = remaining.len();
+    let previous = remaining[(i + len - 1) % len];
+    let next = remaining[(i + 1) % len];
+    let (a, b, c) = (vertices[previous], vertices[remaining[i]], vertices[next]);
+    if turn(a, b, c) <= straight_tolerance(a, b, c) {
+        return false;
+    }
+
+    remaining
+        .iter()
+        .filter(|index| ![previous, remaining[i], next].contains(index))
+        .all(|index| {
+            let point = vertices[*index];
+            turn(a, b, point) < 0.0 || turn(b, c, point) < 0.0 || turn(c, a, point) < 0.0
+        })
+}
+
+// Joins two pieces along an edge they share, if the result is still a convex
+// polygon that isn't too big
+fn merge(vertices: &[Vec2], piece1: &[usize], piece2: &[usize]) -> Option<Vec<usize>> {
+    let len1 = piece1.len();
+    let len2 = piece2.len();
+    if len1 + len2 - 2 > MAX_POLYGON_VERTICES {
+        return None;
+    }
+
+    // The shared edge goes from `a` to `b` in the first piece, and the other
+    // way around in the second one
+    let (k, m) = (0..len1).find_map(|k| {
+        let (a, b) = (piece1[k], piece1[(k + 1) % len1]);
+        (0..len2)
+            .find(|m| piece2[*m] == b && piece2[(m + 1) % len2] == a)
+            .map(|m| (k, m))
+    })?;
+
+    let merged: Vec<usize> = (1..=len1)
+        .map(|offset| piece1[(k + offset) % len1])
+        .chain((2..len2).map(|offset| piece2[(m + offset) % len2]))
+        .collect();
+    let len = merged.len();
+    let convex = (0..len).all(|i| {
+        let previous = vertices[merged[(i + len - 1) % len]];
+        let next = vertices[merged[(i + 1) % len]];
+        let corner = vertices[merged[i]];
+        turn(previous, corner, next) > straight_tolerance(previous, corner, next)
+    });
+    convex.then_some(merged)
+}
+
+// Positive when going from `a` through `b` to `c` turns left
+fn turn(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    (b - a).perp_dot(c - b)
+}
+
+// Turns up to this size count as going straight. Like in `ConvexPolygon::new`
+// it scales with the edges, so it doesn't depend on the size of the polygon.
+fn straight_tolerance(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    f32::EPSILON * (b - a).length() * (c - b).length()
+}
+
+fn segments_touch(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> bool {
+    let sides_of_a = (turn(a1, a2, b1), turn(a1, a2, b2));
+    let sides_of_b = (turn(b1, b2, a1), turn(b1, b2, a2));
+    if sides_of_a.0 * sides_of_a.1 < 0.0 && sides_of_b.0 * sides_of_b.1 < 0.0 {
+        return true;
+    }
+
+    // A point on the other segment
+    let on_segment = |start: Vec2, end: Vec2, point: Vec2| {
+        turn(start, end, point) == 0.0
+            && point.cmpge(start.min(end)).all()
+            && point.cmple(start.max(end)).all()
+    };
+    on_segment(a1, a2, b1)
+        || on_segment(a1, a2, b2)
+        || on_segment(b1, b2, a1)
+        || on_segment(b1, b2, a2)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn total_area(pieces: &[ConvexPolygon]) -> f32 {
+        pieces
+            .iter()
+            .map(|piece| piece.area_centroid_and_inertia().0)
+            .sum()
+    }
+
+    #[test]
+    fn decomposes_l_and_u_shapes() {
+        let l_shape = [
+            Vec2::ZERO,
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 2.0),
+            Vec2::new(0.0, 2.0),
+        ];
+        assert_eq!(triangulate(&l_shape).unwrap().len(), 4);
+        let pieces = convex_decomposition(&l_shape).unwrap();
+        assert_eq!(pieces.len(), 2);
+        assert_relative_eq!(total_area(&pieces), 3.0, epsilon = 1e-5);
+
+        // The same shape a few tenths of a millimetre across
+        let small_l_shape = l_shape.map(|v| v * 1e-4);
+        assert_eq!(triangulate(&small_l_shape).unwrap().len(), 4);
+        assert_eq!(convex_decomposition(&small_l_shape).unwrap().len(), 2);
+
+        // Clockwise, with an extra vertex in the middle of the bottom edge
+        let mut u_shape = vec![
+            Vec2::ZERO,
+            Vec2::new(1.5, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 2.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 2.0),
+            Vec2::new(0.0, 2.0),
+        ];
+        u_shape.reverse();
+        let pieces = convex_decomposition(&u_shape).unwrap();
+        assert_eq!(pieces.len(), 3);
+        assert_relative_eq!(total_area(&pieces), 5.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn rejects_bad_polygons() {
+        let bow_tie = [
+            Vec2::ZERO,
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 1.0),
+        ];
+        assert_eq!(triangulate(&bow_tie), Err(PolygonError::SelfIntersecting));
+        assert_eq!(
+            triangulate(&[Vec2::ZERO, Vec2::X, Vec2::new(2.0, 0.0)]),
+            Err(PolygonError::Degenerate)
+        );
+        assert_eq!(
+            convex_decomposition(&[Vec2::ZERO, Vec2::X]),
+            Err(PolygonError::TooFewVertices)
+        );
+    }
+}
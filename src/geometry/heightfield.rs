@@ -0,0 +1,168 @@
+use std::{fmt, ops::Range, sync::Arc};
+
+use glam::Vec2;
+
+use super::{Aabb, ChainSegment, Line2D};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightfieldError {
+    TooFewHeights,
+    // `scale.x` has to be positive and finite
+    InvalidSpacing,
+    // A height or `scale.y` is NaN or infinite
+    NonFiniteHeight,
+}
+
+impl fmt::Display for HeightfieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeightfieldError::TooFewHeights => write!(f, "a heightfield needs at least 2 heights"),
+            HeightfieldError::InvalidSpacing => {
+                write!(f, "heightfield samples need a positive, finite spacing")
+            }
+            HeightfieldError::NonFiniteHeight => {
+                write!(f, "the heightfield has a height that isn't finite")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HeightfieldError {}
+
+// Heights sampled every `scale.x` along the x axis from `position`, multiplied
+// by `scale.y`. The heights are shared between clones.
+//
+// It collides like a polyline through the samples from left to right, so only
+// with shapes on the -y side of it. That is above the ground when the y axis
+// points down. Heightfields can't be rotated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heightfield {
+    heights: Arc<[f32]>,
+    min_height: f32,
+    max_height: f32,
+    pub scale: Vec2,
+    pub position: Vec2,
+}
+
+impl Heightfield {
+    pub fn new(heights: impl Into<Arc<[f32]>>, scale: Vec2) -> Result<Self, HeightfieldError> {
+        let heights = heights.into();
+        if heights.len() < 2 {
+            return Err(HeightfieldError::TooFewHeights);
+        }
+        if !(scale.x > 0.0 && scale.x.is_finite()) {
+            return Err(HeightfieldError::InvalidSpacing);
+        }
+        // The bounds are computed from these, and f32::min skips NaN
+        if !scale.y.is_finite() || heights.iter().any(|height| !height.is_finite()) {
+            return Err(HeightfieldError::NonFiniteHeight);
+        }
+        let min_height = heights.iter().copied().fold(f32::MAX, f32::min);
+        let max_height = heights.iter().copied().fold(f32::MIN, f32::max);
+        Ok(Self {
+            heights,
+            min_height,
+            max_height,
+            scale,
+            position: Vec2::ZERO,
+        })
+    }
+
+    pub fn heights(&self) -> &[f32] {
+        &self.heights
+    }
+
+    // Cell `i` is the segment between samples `i` and `i + 1`
+    pub fn cell_count(&self) -> usize {
+        self.heights.len() - 1
+    }
+
+    pub fn point(&self, i: usize) -> Vec2 {
+        self.position + Vec2::new(i as f32, self.heights[i]) * self.scale
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Vec2> + '_ {
+        (0..self.heights.len()).map(|i| self.point(i))
+    }
+
+    // Cells that overlap the x range from `min_x` to `max_x`, without looking
+    // at any of them
+    pub fn cells_in_x_range(&self, min_x: f32, max_x: f32) -> Range<usize> {
+        let cell = |x: f32| ((x - self.position.x) / self.scale.x).floor();
+        let start = cell(min_x).clamp(0.0, self.cell_count() as f32) as usize;
+        let end = (cell(max_x) + 1.0).clamp(0.0, self.cell_count() as f32) as usize;
+        start.min(end)..end
+    }
+
+    // Cell `i` in world space, with its neighbouring samples
+    pub fn segment(&self, i: usize) -> ChainSegment {
+        ChainSegment {
+            line: Line2D::new(self.point(i), self.point(i + 1)),
+            previous: i.checked_sub(1).map(|previous| self.point(previous)),
+            next: (i + 2 < self.heights.len()).then(|| self.point(i + 2)),
+        }
+    }
+
+    pub fn segments_in_x_range(
+        &self,
+        min_x: f32,
+        max_x: f32,
+    ) -> impl Iterator<Item = ChainSegment> + '_ {
+        self.cells_in_x_range(min_x, max_x).map(|i| self.segment(i))
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        let width = self.cell_count() as f32 * self.scale.x;
+        let low = self.min_height * self.scale.y;
+        let high = self.max_height * self.scale.y;
+        Aabb::new(
+            self.position + Vec2::new(0.0, low.min(high)),
+            self.position + Vec2::new(width, low.max(high)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_cells_by_x() {
+        let mut heightfield =
+            Heightfield::new(vec![0.0, 1.0, 2.0, 1.0, 0.0], Vec2::new(2.0, -1.0)).unwrap();
+        heightfield.position = Vec2::new(10.0, 5.0);
+        assert_eq!(heightfield.cells_in_x_range(12.5, 13.0), 1..2);
+        assert_eq!(heightfield.cells_in_x_range(11.0, 14.0), 0..3);
+        assert_eq!(heightfield.cells_in_x_range(0.0, 9.0), 0..0);
+        assert_eq!(heightfield.cells_in_x_range(17.0, 30.0), 3..4);
+        assert_eq!(heightfield.cells_in_x_range(19.0, 30.0), 4..4);
+        assert_eq!(heightfield.point(2), Vec2::new(14.0, 3.0));
+        assert_eq!(
+            heightfield.aabb(),
+            Aabb::new(Vec2::new(10.0, 3.0), Vec2::new(18.0, 5.0))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let scale = Vec2::new(1.0, -1.0);
+        assert_eq!(
+            Heightfield::new(vec![0.0], scale),
+            Err(HeightfieldError::TooFewHeights)
+        );
+        for spacing in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert_eq!(
+                Heightfield::new(vec![0.0; 2], Vec2::new(spacing, 1.0)),
+                Err(HeightfieldError::InvalidSpacing)
+            );
+        }
+        assert_eq!(
+            Heightfield::new(vec![0.0, f32::NAN, 1.0], scale),
+            Err(HeightfieldError::NonFiniteHeight)
+        );
+        assert_eq!(
+            Heightfield::new(vec![0.0; 2], Vec2::new(1.0, f32::NAN)),
+            Err(HeightfieldError::NonFiniteHeight)
+        );
+    }
+}
@@ -1,14 +1,121 @@
+mod capsule;
 mod circle;
+mod convex_polygon;
+mod decomposition;
+mod heightfield;
 mod line_2d;
+mod polyline;
 mod ray_2d;
 mod rect;
+mod segment;
+mod trimesh;
 
+pub use capsule::Capsule;
 pub use circle::Circle;
+pub use convex_polygon::{ConvexPolygon, PolygonError, MAX_POLYGON_VERTICES};
+pub use decomposition::{convex_decomposition, triangulate};
 use glam::Vec2;
+pub use heightfield::{Heightfield, HeightfieldError};
 pub use line_2d::Line2D;
+pub use polyline::{ChainSegment, Polyline, PolylineError};
 pub use ray_2d::{Ray2D, RaycastResult2D};
 pub use rect::{Aabb, Box2D};
+pub use segment::Segment;
+pub use trimesh::TriMesh;
 
 pub trait Convex<const N: usize> {
     fn get_vertices(&self) -> [Vec2; N];
 }
+
+// Convex shapes described by their furthest point in any direction, which is
+// all GJK needs to know about them
+pub trait SupportMap {
+    fn support_point(&self, direction: Vec2) -> Vec2;
+}
+
+impl SupportMap for Vec2 {
+    fn support_point(&self, _direction: Vec2) -> Vec2 {
+        *self
+    }
+}
+
+// The convex hull of the points
+impl<const N: usize> SupportMap for [Vec2; N] {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        let mut best = self[0];
+        for vertex in self[1..].iter().copied() {
+            if vertex.dot(direction) > best.dot(direction) {
+                best = vertex;
+            }
+        }
+        best
+    }
+}
+
+impl SupportMap for Line2D {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        if self.start.dot(direction) >= self.end.dot(direction) {
+            self.start
+        } else {
+            self.end
+        }
+    }
+}
+
+// The convex hull of the polyline
+impl SupportMap for Polyline {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        self.vertices()
+            .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+            .unwrap_or(self.position)
+    }
+}
+
+// The convex hull of the heightfield
+impl SupportMap for Heightfield {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        self.points()
+            .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+            .unwrap_or(self.position)
+    }
+}
+
+// The convex hull of the mesh
+impl SupportMap for TriMesh {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        self.triangles()
+            .map(|triangle| triangle.support_point(direction))
+            .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+            .unwrap_or(self.position)
+    }
+}
+
+impl SupportMap for Capsule {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        self.segment().support_point(direction) + direction.normalize_or_zero() * self.radius
+    }
+}
+
+impl SupportMap for Circle {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        self.center + direction.normalize_or_zero() * self.radius
+    }
+}
+
+impl SupportMap for Aabb {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        self.get_vertices().support_point(direction)
+    }
+}
+
+impl SupportMap for ConvexPolygon {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        self.get_vertices().support_point(direction)
+    }
+}
+
+impl SupportMap for Box2D {
+    fn support_point(&self, direction: Vec2) -> Vec2 {
+        self.get_vertices().support_point(direction)
+    }
+}
@@ -0,0 +1,157 @@
+use std::{fmt, sync::Arc};
+
+use glam::Vec2;
+
+use super::Line2D;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolylineError {
+    // Open polylines need at least 2 vertices, closed ones 3
+    TooFewVertices,
+}
+
+impl fmt::Display for PolylineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolylineError::TooFewVertices => write!(
+                f,
+                "a polyline needs at least 2 vertices, or 3 when it is closed"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PolylineError {}
+
+// Connected segments through a list of vertices, relative to `position` and
+// unrotated. The vertices are shared between clones.
+//
+// Polylines are one-sided: they only collide with shapes on the side their
+// edge normals point to, which is to the right of the direction of each
+// segment. That is the outside of a counter-clockwise loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyline {
+    vertices: Arc<[Vec2]>,
+    closed: bool,
+    pub position: Vec2,
+    pub rotation: f32,
+}
+
+impl Polyline {
+    pub fn new(vertices: impl Into<Arc<[Vec2]>>) -> Result<Self, PolylineError> {
+        let vertices = vertices.into();
+        if vertices.len() < 2 {
+            return Err(PolylineError::TooFewVertices);
+        }
+        Ok(Self {
+            vertices,
+            closed: false,
+            position: Vec2::ZERO,
+            rotation: 0.0,
+        })
+    }
+
+    // Also connects the last vertex back to the first one
+    pub fn closed(vertices: impl Into<Arc<[Vec2]>>) -> Result<Self, PolylineError> {
+        let vertices = vertices.into();
+        if vertices.len() < 3 {
+            return Err(PolylineError::TooFewVertices);
+        }
+        Ok(Self {
+            closed: true,
+            ..Self::new(vertices)?
+        })
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn local_vertices(&self) -> &[Vec2] {
+        &self.vertices
+    }
+
+    pub fn vertex(&self, i: usize) -> Vec2 {
+        self.position + Vec2::from_angle(self.rotation).rotate(self.vertices[i])
+    }
+
+    pub fn vertices(&self) -> impl Iterator<Item = Vec2> + '_ {
+        (0..self.vertices.len()).map(|i| self.vertex(i))
+    }
+
+    pub fn segment_count(&self) -> usize {
+        if self.closed {
+            self.vertices.len()
+        } else {
+            self.vertices.len() - 1
+        }
+    }
+
+    // Segment `i` in world space, with its neighbouring vertices
+    pub fn segment(&self, i: usize) -> ChainSegment {
+        let len = self.vertices.len();
+        let previous = if self.closed || i > 0 {
+            Some(self.vertex((i + len - 1) % len))
+        } else {
+            None
+        };
+        let next = if self.closed || i + 2 < len {
+            Some(self.vertex((i + 2) % len))
+        } else {
+            None
+        };
+        ChainSegment {
+            line: Line2D::new(self.vertex(i), self.vertex((i + 1) % len)),
+            previous,
+            next,
+        }
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = ChainSegment> + '_ {
+        (0..self.segment_count()).map(|i| self.segment(i))
+    }
+}
+
+// A segment of a polyline. The neighbouring vertices, called ghost vertices,
+// tell which normals at the ends of the segment are really on the surface.
+// They are `None` at the open ends of a polyline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChainSegment {
+    pub line: Line2D,
+    pub previous: Option<Vec2>,
+    pub next: Option<Vec2>,
+}
+
+impl ChainSegment {
+    // Points to the side the segment collides on
+    pub fn normal(&self) -> Vec2 {
+        let edge = self.line.to_vec2();
+        Vec2::new(edge.y, -edge.x).normalize_or_zero()
+    }
+
+    // Whether `point` is on the side the segment collides on
+    pub fn faces(&self, point: Vec2) -> bool {
+        self.normal().dot(point - self.line.start) >= 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_too_few_vertices() {
+        assert_eq!(
+            Polyline::new(vec![Vec2::ZERO]),
+            Err(PolylineError::TooFewVertices)
+        );
+        assert_eq!(
+            Polyline::closed(vec![Vec2::ZERO, Vec2::X]),
+            Err(PolylineError::TooFewVertices)
+        );
+        assert!(Polyline::new(vec![Vec2::ZERO, Vec2::X]).is_ok());
+        assert!(Polyline::closed(vec![Vec2::ZERO, Vec2::X, Vec2::Y])
+            .unwrap()
+            .is_closed());
+    }
+}
@@ -2,7 +2,7 @@ use glam::Vec2;
 
 use crate::math::Vec2Ext;
 
-use super::Convex;
+use super::{Circle, Convex, Ray2D};
 
 macro_rules! impl_rect_common_methods {
     () => {
@@ -14,10 +14,11 @@ macro_rules! impl_rect_common_methods {
             self.size() / 2.0
         }
 
+        // Keeps the center in place
         pub fn set_size(&mut self, new_size: Vec2) {
-            let size_delta = new_size - self.size();
-            self.min -= size_delta;
-            self.max += size_delta;
+            let half_delta = (new_size - self.size()) / 2.0;
+            self.min -= half_delta;
+            self.max += half_delta;
         }
 
         pub fn center(self) -> Vec2 {
@@ -50,14 +51,116 @@ impl Aabb {
         let max = center + half_size;
         Self { min, max }
     }
+
+    pub fn from_points(points: impl IntoIterator<Item = Vec2>) -> Aabb {
+        let mut points = points.into_iter();
+        let first = points.next().unwrap_or(Vec2::ZERO);
+        points.fold(Self::new(first, first), |aabb, point| {
+            Self::new(aabb.min.min(point), aabb.max.max(point))
+        })
+    }
+
+    // Touching boxes count as intersecting
+    pub fn intersects(self, other: Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+    }
+
+    pub fn intersection(self, other: Aabb) -> Option<Aabb> {
+        let intersection = Self::new(self.min.max(other.min), self.max.min(other.max));
+        if intersection.min.x > intersection.max.x || intersection.min.y > intersection.max.y {
+            return None;
+        }
+        Some(intersection)
+    }
+
+    pub fn contains_point(self, point: Vec2) -> bool {
+        self.min.x <= point.x
+            && self.min.y <= point.y
+            && self.max.x >= point.x
+            && self.max.y >= point.y
+    }
+
+    pub fn contains(self, other: Aabb) -> bool {
+        self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && self.max.x >= other.max.x
+            && self.max.y >= other.max.y
+    }
+
+    pub fn merged(self, other: Aabb) -> Aabb {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn expanded_to(self, point: Vec2) -> Aabb {
+        Self::new(self.min.min(point), self.max.max(point))
+    }
+
+    pub fn loosened(self, margin: f32) -> Aabb {
+        Self::new(
+            self.min - Vec2::splat(margin),
+            self.max + Vec2::splat(margin),
+        )
+    }
+
+    pub fn translated(self, offset: Vec2) -> Aabb {
+        Self::new(self.min + offset, self.max + offset)
+    }
+
+    // Covers the box over its whole motion by `displacement`
+    pub fn swept(self, displacement: Vec2) -> Aabb {
+        self.merged(self.translated(displacement))
+    }
+
+    pub fn area(self) -> f32 {
+        let size = self.size();
+        size.x * size.y
+    }
+
+    pub fn perimeter(self) -> f32 {
+        let size = self.size();
+        2.0 * (size.x + size.y)
+    }
+
+    // Slab test. Returns the distance along the ray at which it enters the
+    // box, or zero if it starts inside.
+    pub fn cast_ray(self, ray: Ray2D, max_t: f32) -> Option<f32> {
+        let mut t_min = 0.0_f32;
+        let mut t_max = max_t;
+        for i in 0..2 {
+            if ray.direction[i] == 0.0 {
+                if ray.origin[i] < self.min[i] || ray.origin[i] > self.max[i] {
+                    return None;
+                }
+                continue;
+            }
+
+            let inv_direction = 1.0 / ray.direction[i];
+            let t1 = (self.min[i] - ray.origin[i]) * inv_direction;
+            let t2 = (self.max[i] - ray.origin[i]) * inv_direction;
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
 }
 
+impl From<Circle> for Aabb {
+    fn from(c: Circle) -> Self {
+        Self::from_center_and_half_size(c.center, Vec2::splat(c.radius))
+    }
+}
+
+// Tight bounds of the rotated box
 impl From<Box2D> for Aabb {
     fn from(b: Box2D) -> Self {
-        Self {
-            min: b.min,
-            max: b.max,
-        }
+        Self::from_points(b.get_vertices())
     }
 }
 
@@ -109,3 +212,83 @@ impl Convex<4> for Box2D {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn aabb_set_operations() {
+        let a = Aabb::new(Vec2::ZERO, Vec2::splat(2.0));
+        let b = Aabb::new(Vec2::ONE, Vec2::splat(3.0));
+        let c = Aabb::new(Vec2::splat(5.0), Vec2::splat(6.0));
+
+        assert_eq!(
+            a.intersection(b),
+            Some(Aabb::new(Vec2::ONE, Vec2::splat(2.0)))
+        );
+        assert_eq!(a.intersection(c), None);
+        assert_eq!(a.merged(b), Aabb::new(Vec2::ZERO, Vec2::splat(3.0)));
+        assert!(a.merged(c).contains(b));
+        assert!(!a.contains(b));
+        assert_eq!(
+            a.expanded_to(Vec2::new(-1.0, 1.0)).min,
+            Vec2::new(-1.0, 0.0)
+        );
+        assert_eq!(
+            a.swept(Vec2::new(3.0, -1.0)),
+            Aabb::new(Vec2::new(0.0, -1.0), Vec2::new(5.0, 2.0))
+        );
+    }
+
+    #[test]
+    fn set_size_keeps_center() {
+        let mut aabb = Aabb::new(Vec2::ZERO, Vec2::splat(2.0));
+        aabb.set_size(Vec2::new(4.0, 1.0));
+        assert_eq!(aabb, Aabb::new(Vec2::new(-1.0, 0.5), Vec2::new(3.0, 1.5)));
+
+        let mut b = Box2D::new(Vec2::ZERO, Vec2::splat(2.0), 0.5);
+        b.set_size(Vec2::new(4.0, 1.0));
+        assert_eq!(b.center(), Vec2::ONE);
+        assert_eq!(b.size(), Vec2::new(4.0, 1.0));
+        assert_eq!(b.rotation, 0.5);
+    }
+
+    #[test]
+    fn aabb_of_rotated_box_is_tight() {
+        let b = Box2D::new(Vec2::splat(-1.0), Vec2::splat(1.0), f32::to_radians(45.0));
+        let aabb = Aabb::from(b);
+        assert_relative_eq!(aabb.max.x, 2.0_f32.sqrt(), epsilon = 1e-5);
+        assert_relative_eq!(aabb.min.y, -(2.0_f32.sqrt()), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn aabb_of_box_follows_rotation() {
+        let b = Box2D::new(Vec2::ZERO, Vec2::new(4.0, 2.0), 0.0);
+        assert_eq!(Aabb::from(b), Aabb::new(b.min, b.max));
+
+        // Turned on its side around its center
+        let b = Box2D::new(Vec2::ZERO, Vec2::new(4.0, 2.0), f32::to_radians(90.0));
+        let aabb = Aabb::from(b);
+        assert!(aabb.min.abs_diff_eq(Vec2::new(1.0, -1.0), 1e-5));
+        assert!(aabb.max.abs_diff_eq(Vec2::new(3.0, 3.0), 1e-5));
+    }
+
+    #[test]
+    fn aabb_ray_slab_test() {
+        let aabb = Aabb::new(Vec2::new(2.0, -1.0), Vec2::new(4.0, 1.0));
+        let ray = Ray2D::new(Vec2::ZERO, Vec2::X);
+        assert_eq!(aabb.cast_ray(ray, f32::MAX), Some(2.0));
+        assert_eq!(aabb.cast_ray(ray, 1.0), None);
+        assert_eq!(
+            aabb.cast_ray(Ray2D::new(Vec2::new(3.0, 0.0), Vec2::X), 1.0),
+            Some(0.0)
+        );
+        assert_eq!(
+            aabb.cast_ray(Ray2D::new(Vec2::new(0.0, 2.0), Vec2::X), f32::MAX),
+            None
+        );
+    }
+}
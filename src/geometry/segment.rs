@@ -0,0 +1,32 @@
+use glam::Vec2;
+
+use super::Line2D;
+
+// A line segment from `a` to `b`, relative to `position` and unrotated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub a: Vec2,
+    pub b: Vec2,
+    pub position: Vec2,
+    pub rotation: f32,
+}
+
+impl Segment {
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self {
+            a,
+            b,
+            position: Vec2::ZERO,
+            rotation: 0.0,
+        }
+    }
+
+    // The segment in world space
+    pub fn line(&self) -> Line2D {
+        let rotation_vec = Vec2::from_angle(self.rotation);
+        Line2D::new(
+            self.position + rotation_vec.rotate(self.a),
+            self.position + rotation_vec.rotate(self.b),
+        )
+    }
+}
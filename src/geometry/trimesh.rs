@@ -0,0 +1,175 @@
+use std::{collections::HashMap, sync::Arc};
+
+use glam::Vec2;
+
+use super::{Aabb, ConvexPolygon, PolygonError};
+use crate::collision::DynamicAabbTree;
+
+// Triangles relative to `position` and unrotated, with a bounding volume
+// hierarchy to find the ones near a query quickly. The triangles and the tree
+// are shared between clones.
+#[derive(Debug, Clone)]
+pub struct TriMesh {
+    triangles: Arc<[ConvexPolygon]>,
+    // Whether edge `i` of each triangle, from its vertex `i` to the next, is
+    // on the outline of the mesh rather than shared with another triangle
+    boundary_edges: Arc<[[bool; 3]]>,
+    bvh: Arc<DynamicAabbTree<usize>>,
+    local_aabb: Aabb,
+    pub position: Vec2,
+    pub rotation: f32,
+}
+
+impl TriMesh {
+    // Each triangle is three indices into `vertices`, in either winding
+    pub fn new(vertices: &[Vec2], triangles: &[[usize; 3]]) -> Result<Self, PolygonError> {
+        if triangles.is_empty() {
+            return Err(PolygonError::EmptyMesh);
+        }
+
+        // Counter-clockwise, like the triangles will be
+        let triangles: Vec<_> = triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                let [pa, pb, pc] = [a, b, c].map(|i| vertices.get(i).copied());
+                let (Some(pa), Some(pb), Some(pc)) = (pa, pb, pc) else {
+                    return Err(PolygonError::VertexIndexOutOfBounds);
+                };
+                if (pb - pa).perp_dot(pc - pb) < 0.0 {
+                    Ok([a, c, b])
+                } else {
+                    Ok([a, b, c])
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        // Edges are shared when their vertices are, whichever way round
+        let edge_key = |indices: [usize; 3], i: usize| {
+            let (a, b) = (indices[i], indices[(i + 1) % 3]);
+            (a.min(b), a.max(b))
+        };
+        let mut edge_counts = HashMap::new();
+        for indices in triangles.iter() {
+            for i in 0..3 {
+                *edge_counts.entry(edge_key(*indices, i)).or_insert(0) += 1;
+            }
+        }
+        let boundary_edges = triangles
+            .iter()
+            .map(|indices| [0, 1, 2].map(|i| edge_counts[&edge_key(*indices, i)] == 1))
+            .collect();
+
+        let triangles = triangles
+            .iter()
+            .map(|indices| ConvexPolygon::new(&indices.map(|i| vertices[i])))
+            .collect::<Result<Arc<[_]>, _>>()?;
+
+        // The triangles never move relative to each other, so the tree needs
+        // no margin
+        let mut bvh = DynamicAabbTree::with_margin(0.0);
+        for (i, triangle) in triangles.iter().enumerate() {
+            bvh.insert(Aabb::from_points(triangle.vertices()), i);
+        }
+        let local_aabb =
+            Aabb::from_points(triangles.iter().flat_map(|triangle| triangle.vertices()));
+        Ok(Self {
+            triangles,
+            boundary_edges,
+            bvh: Arc::new(bvh),
+            local_aabb,
+            position: Vec2::ZERO,
+            rotation: 0.0,
+        })
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn local_triangles(&self) -> &[ConvexPolygon] {
+        &self.triangles
+    }
+
+    // Edge `edge` of a triangle goes from its vertex `edge` to the next one
+    pub fn is_boundary_edge(&self, triangle: usize, edge: usize) -> bool {
+        self.boundary_edges[triangle][edge]
+    }
+
+    // Triangle `i` in world space
+    pub fn triangle(&self, i: usize) -> ConvexPolygon {
+        let mut triangle = self.triangles[i];
+        triangle.position = self.position;
+        triangle.rotation = self.rotation;
+        triangle
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = ConvexPolygon> + '_ {
+        (0..self.triangles.len()).map(|i| self.triangle(i))
+    }
+
+    // Calls `callback` with the index of every triangle whose bounds overlap
+    // `aabb`, until it returns false
+    pub fn triangles_in_aabb(&self, aabb: Aabb, mut callback: impl FnMut(usize) -> bool) {
+        let corners = [
+            aabb.min,
+            Vec2::new(aabb.max.x, aabb.min.y),
+            aabb.max,
+            Vec2::new(aabb.min.x, aabb.max.y),
+        ];
+        let local_aabb = Aabb::from_points(corners.map(|corner| self.to_local(corner)));
+        self.bvh.query_aabb(local_aabb, |_, i| callback(i));
+    }
+
+    // The tree holds the index of each triangle, in the space of the mesh
+    pub fn bvh(&self) -> &DynamicAabbTree<usize> {
+        &self.bvh
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        let Aabb { min, max } = self.local_aabb;
+        Aabb::from_points(
+            [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)]
+                .map(|corner| self.to_world(corner)),
+        )
+    }
+
+    pub fn to_local(&self, point: Vec2) -> Vec2 {
+        Vec2::from_angle(-self.rotation).rotate(point - self.position)
+    }
+
+    pub fn to_world(&self, local_point: Vec2) -> Vec2 {
+        self.position + Vec2::from_angle(self.rotation).rotate(local_point)
+    }
+}
+
+// Meshes built from the same triangles are equal, the tree doesn't matter
+impl PartialEq for TriMesh {
+    fn eq(&self, other: &Self) -> bool {
+        self.triangles == other.triangles
+            && self.position == other.position
+            && self.rotation == other.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_rejects_bad_triangles() {
+        let vertices = [Vec2::ZERO, Vec2::X, Vec2::Y];
+        assert_eq!(TriMesh::new(&vertices, &[]), Err(PolygonError::EmptyMesh));
+        assert_eq!(
+            TriMesh::new(&vertices, &[[0, 1, 3]]),
+            Err(PolygonError::VertexIndexOutOfBounds)
+        );
+        assert_eq!(
+            TriMesh::new(&vertices, &[[0, 1, 1]]),
+            Err(PolygonError::Degenerate)
+        );
+
+        // Either winding works
+        let mesh = TriMesh::new(&vertices, &[[0, 1, 2], [0, 2, 1]]).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+    }
+}
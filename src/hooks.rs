@@ -0,0 +1,41 @@
+use crate::{
+    collision::{ColliderSet, Collision, CollisionPair},
+    RigidBodySet,
+};
+
+pub struct PairFilterContext<'a> {
+    pub bodies: &'a RigidBodySet,
+    pub colliders: &'a ColliderSet,
+    pub pair: CollisionPair,
+}
+
+pub struct ContactModificationContext<'a> {
+    pub bodies: &'a RigidBodySet,
+    pub colliders: &'a ColliderSet,
+    // The friction, restitution, surface velocity and manifold of the contact
+    // can all be changed before the solver uses them
+    pub collision: &'a mut Collision,
+}
+
+impl ContactModificationContext<'_> {
+    pub fn flip_normal(&mut self) {
+        self.collision.manifold.normal = -self.collision.manifold.normal;
+    }
+}
+
+// User code that the pipeline calls while looking for collisions
+pub trait PhysicsHooks {
+    // Called for every pair of colliders that may collide, before the
+    // narrowphase. Returning false skips the pair.
+    fn filter_contact_pair(&self, _context: &PairFilterContext) -> bool {
+        true
+    }
+
+    // Called for every contact before it is resolved. Returning false
+    // removes the contact.
+    fn modify_contact(&self, _context: &mut ContactModificationContext) -> bool {
+        true
+    }
+}
+
+impl PhysicsHooks for () {}
@@ -2,12 +2,16 @@ pub mod collision;
 pub mod dynamics;
 pub mod force;
 pub mod geometry;
+pub mod hooks;
 pub mod math;
 pub mod pipeline;
+pub mod query;
 
 pub use dynamics::*;
 pub use force::*;
 pub use generational_arena;
 pub use geometry::*;
 pub use glam;
+pub use hooks::*;
 pub use pipeline::*;
+pub use query::*;
@@ -1,22 +1,68 @@
+use std::collections::HashSet;
+
 use glam::Vec2;
 
 use crate::{
-    collision::{ColliderSet, Collision, CollisionManifold},
-    ForceGeneratorSet, ForceRegistry, RigidBody, RigidBodySet,
+    collision::{
+        BroadPhase, Collider, ColliderHandle, ColliderSet, Collision, CollisionEvent,
+        CollisionManifold, CollisionPair, ContactForceEvent, DynamicTreeBroadPhase,
+        IntersectionEvent, PairStatus, PairTracker,
+    },
+    ContactModificationContext, ForceGeneratorSet, ForceRegistry, PairFilterContext, PhysicsHooks,
+    RigidBody, RigidBodyHandle, RigidBodySet,
 };
 
 pub struct PhysicsPipeline {
     fixed_dt: f32,
+    broad_phase: Box<dyn BroadPhase>,
+    hooks: Box<dyn PhysicsHooks>,
+    // Pairs of colliders whose bounding boxes overlap
+    candidate_pairs: Vec<(ColliderHandle, ColliderHandle)>,
     collisions: Vec<Collision>,
+    // Overlapping pairs where at least one collider is a sensor
+    intersections: Vec<CollisionPair>,
+    collision_tracker: PairTracker,
+    intersection_tracker: PairTracker,
+    // One-way contacts that were rejected when they started. They stay
+    // rejected until the colliders stop touching.
+    one_way_rejected_pairs: HashSet<(ColliderHandle, ColliderHandle)>,
+    collision_events: Vec<CollisionEvent>,
+    // Off by default, as they would be queued for every touching pair on
+    // every step
+    persisted_collision_events: bool,
+    intersection_events: Vec<IntersectionEvent>,
+    contact_force_events: Vec<ContactForceEvent>,
 }
 
 impl PhysicsPipeline {
     const IMPULSE_ITERATIONS: u32 = 6;
+    // How deep a one-way contact may start beyond what its velocity explains
+    const ONE_WAY_SLOP: f32 = 0.01;
+    // Penetration that is left alone to keep resting contacts stable
+    const PENETRATION_SLOP: f32 = 0.01;
+    // Part of the remaining penetration that is pushed out each step. Less
+    // than all of it, so stacks settle instead of jittering.
+    const POSITION_CORRECTION_PERCENT: f32 = 0.4;
 
     pub fn new(fixed_dt: f32) -> Self {
+        Self::with_broad_phase(fixed_dt, Box::new(DynamicTreeBroadPhase::new()))
+    }
+
+    pub fn with_broad_phase(fixed_dt: f32, broad_phase: Box<dyn BroadPhase>) -> Self {
         Self {
             fixed_dt,
+            broad_phase,
+            hooks: Box::new(()),
+            candidate_pairs: Vec::new(),
             collisions: Vec::new(),
+            intersections: Vec::new(),
+            collision_tracker: PairTracker::default(),
+            intersection_tracker: PairTracker::default(),
+            one_way_rejected_pairs: HashSet::new(),
+            collision_events: Vec::new(),
+            persisted_collision_events: false,
+            intersection_events: Vec::new(),
+            contact_force_events: Vec::new(),
         }
     }
 
@@ -43,55 +89,258 @@ impl PhysicsPipeline {
     ) {
         // Update forces
         force_registry.update_forces(bodies, generators, self.fixed_dt);
+        // Contacts are solved with the velocities the forces lead to, so that
+        // they cancel gravity instead of lagging a step behind it
         for (_, body) in bodies.iter_mut() {
             body.add_force(gravity * body.mass());
+            body.integrate_forces(self.fixed_dt);
         }
 
         // Find collisions
         self.collisions.clear();
-        for (i, (body_handle1, body1)) in bodies.iter().enumerate() {
-            for (body_handle2, body2) in bodies.iter().skip(i + 1) {
-                if body1.has_infinite_mass() && body2.has_infinite_mass() {
-                    continue;
-                }
+        self.intersections.clear();
+        let previous_rejected_pairs = std::mem::take(&mut self.one_way_rejected_pairs);
+        self.broad_phase.update(colliders);
+        let mut candidate_pairs = std::mem::take(&mut self.candidate_pairs);
+        candidate_pairs.clear();
+        self.broad_phase.find_pairs(&mut candidate_pairs);
+        // Broadphases report pairs in no particular order. Sorting keeps the
+        // solver order, and with it the simulation, the same for all of them.
+        candidate_pairs.sort_unstable();
+        for (collider_handle1, collider_handle2) in candidate_pairs.iter().copied() {
+            let coll1 = &colliders[collider_handle1];
+            let coll2 = &colliders[collider_handle2];
+            let body_handle1 = coll1.parent;
+            let body_handle2 = coll2.parent;
 
-                let (Some(coll_handle_1), Some(coll_handle_2)) = (body1.collider, body2.collider) else {
-                    continue;
-                };
+            // Colliders of the same body never collide with each other
+            if body_handle1.is_some() && body_handle1 == body_handle2 {
+                continue;
+            }
+
+            if !coll1.collision_groups.test(coll2.collision_groups) {
+                continue;
+            }
+
+            // Colliders without a parent act as static geometry
+            let is_static = |handle: Option<RigidBodyHandle>| {
+                handle.is_none_or(|handle| bodies[handle].has_infinite_mass())
+            };
+            if is_static(body_handle1) && is_static(body_handle2) {
+                continue;
+            }
 
-                let coll1 = &colliders[coll_handle_1];
-                let coll2 = &colliders[coll_handle_2];
+            let pair = CollisionPair {
+                collider_handle1,
+                collider_handle2,
+                body_handle1,
+                body_handle2,
+            };
+            let filter_context = PairFilterContext {
+                bodies,
+                colliders,
+                pair,
+            };
+            if !self.hooks.filter_contact_pair(&filter_context) {
+                continue;
+            }
+
+            // Sensors only report overlaps and never collide
+            if coll1.is_sensor || coll2.is_sensor {
+                if !(coll1.is_sensor && coll2.is_sensor) && coll1.test_collision(coll2).is_some() {
+                    self.intersections.push(pair);
+                }
+                continue;
+            }
+
+            let Some(manifold) = coll1.test_collision(coll2) else {
+                continue;
+            };
 
-                let Some(manifold) = coll1.test_collision(coll2) else {
+            if coll1.one_way_direction.is_some() || coll2.one_way_direction.is_some() {
+                let is_rejected = previous_rejected_pairs.contains(&pair.key())
+                    || (!self.collision_tracker.contains(&pair)
+                        && !self.accepts_one_way_contact(bodies, &pair, coll1, coll2, &manifold));
+                if is_rejected {
+                    self.one_way_rejected_pairs.insert(pair.key());
                     continue;
-                };
+                }
+            }
 
-                let collision = Collision {
-                    body_handle1,
-                    body_handle2,
-                    manifold,
-                };
+            let body_restitution = |handle: Option<RigidBodyHandle>| {
+                handle.map_or(1.0, |handle| bodies[handle].restitution())
+            };
+            let surface_velocity = |collider: &Collider| {
+                Vec2::from_angle(collider.world_rotation(bodies)) * collider.material.tangent_speed
+            };
+            let mut collision = Collision {
+                body_handle1,
+                body_handle2,
+                collider_handle1,
+                collider_handle2,
+                manifold,
+                friction: coll1.material.combined_friction(&coll2.material),
+                restitution: coll1
+                    .material
+                    .combined_restitution(&coll2.material)
+                    .min(body_restitution(body_handle1))
+                    .min(body_restitution(body_handle2)),
+                surface_velocity: surface_velocity(coll2) - surface_velocity(coll1),
+                normal_impulse: 0.0,
+                tangent_impulse: 0.0,
+            };
 
-                self.collisions.push(collision);
+            let mut modification_context = ContactModificationContext {
+                bodies,
+                colliders,
+                collision: &mut collision,
+            };
+            if !self.hooks.modify_contact(&mut modification_context) {
+                continue;
             }
+
+            self.collisions.push(collision);
         }
+        self.candidate_pairs = candidate_pairs;
+
+        self.update_events();
 
         // Resolve collisions via iterative impulse resolution
         for _ in 0..Self::IMPULSE_ITERATIONS {
-            for collision in self.collisions.iter() {
-                let (Some(rb1), Some(rb2)) = bodies.get2_mut(collision.body_handle1, collision.body_handle2) else {
-                        panic!("Rigid body not found");
-                    };
-                self.apply_impulse(rb1, rb2, &collision.manifold);
+            for collision in self.collisions.iter_mut() {
+                let (rb1, rb2) =
+                    bodies.get_pair_mut(collision.body_handle1, collision.body_handle2);
+                let (normal_impulse, tangent_impulse) = Self::apply_impulse(rb1, rb2, collision);
+                collision.normal_impulse += normal_impulse;
+                collision.tangent_impulse += tangent_impulse;
             }
         }
 
-        // Update velocities
+        self.update_contact_force_events(colliders);
+
+        // Impulses only stop bodies from moving further into each other. The
+        // overlap left by a landing, or by a stack the impulses couldn't quite
+        // hold up, is pushed out here so that bodies don't sink. Only the
+        // positions move, so this adds no energy.
+        for collision in self.collisions.iter() {
+            let (rb1, rb2) = bodies.get_pair_mut(collision.body_handle1, collision.body_handle2);
+            Self::correct_positions(rb1, rb2, &collision.manifold, colliders);
+        }
+
+        // Update positions
         for (_, body) in bodies.iter_mut() {
-            body.physics_update(self.fixed_dt, colliders);
+            body.integrate_velocity(self.fixed_dt, colliders);
+        }
+    }
+
+    // Called when a contact with a one-way collider starts. The contact is kept
+    // if its normal points along the allowed direction and the colliders
+    // weren't already overlapping on the previous step.
+    fn accepts_one_way_contact(
+        &self,
+        bodies: &RigidBodySet,
+        pair: &CollisionPair,
+        coll1: &Collider,
+        coll2: &Collider,
+        manifold: &CollisionManifold,
+    ) -> bool {
+        let velocity = |handle: Option<RigidBodyHandle>| {
+            handle.map_or(Vec2::ZERO, |handle| bodies[handle].linear_velocity)
+        };
+        // Velocity of the second collider relative to the first
+        let relative_vel = velocity(pair.body_handle2) - velocity(pair.body_handle1);
+        let approach_speed = -relative_vel.dot(manifold.normal);
+        let max_depth = approach_speed.max(0.0) * self.fixed_dt + Self::ONE_WAY_SLOP;
+        if manifold.depth > max_depth {
+            return false;
+        }
+
+        // The normal points from the first collider to the second
+        let faces_allowed_side = |collider: &Collider, normal: Vec2| {
+            collider.one_way_direction.is_none_or(|direction| {
+                let rotation = Vec2::from_angle(collider.world_rotation(bodies));
+                rotation.rotate(direction).dot(normal) > 0.0
+            })
+        };
+        faces_allowed_side(coll1, manifold.normal) && faces_allowed_side(coll2, -manifold.normal)
+    }
+
+    fn update_events(&mut self) {
+        let collision_events = &mut self.collision_events;
+        let persisted_collision_events = self.persisted_collision_events;
+        let pairs = self.collisions.iter().map(Collision::pair);
+        self.collision_tracker
+            .update(pairs, |status, pair| match status {
+                PairStatus::Started => collision_events.push(CollisionEvent::Started(pair)),
+                PairStatus::Persisted if persisted_collision_events => {
+                    collision_events.push(CollisionEvent::Persisted(pair))
+                }
+                PairStatus::Persisted => (),
+                PairStatus::Stopped => collision_events.push(CollisionEvent::Stopped(pair)),
+            });
+
+        let intersection_events = &mut self.intersection_events;
+        let pairs = self.intersections.iter().copied();
+        self.intersection_tracker
+            .update(pairs, |status, pair| match status {
+                PairStatus::Started => intersection_events.push(IntersectionEvent::Started(pair)),
+                PairStatus::Persisted => (),
+                PairStatus::Stopped => intersection_events.push(IntersectionEvent::Stopped(pair)),
+            });
+    }
+
+    fn update_contact_force_events(&mut self, colliders: &ColliderSet) {
+        for collision in self.collisions.iter() {
+            let exceeds_threshold = |handle| {
+                colliders[handle]
+                    .contact_force_event_threshold
+                    .is_some_and(|threshold| collision.total_impulse() > threshold)
+            };
+            if exceeds_threshold(collision.collider_handle1)
+                || exceeds_threshold(collision.collider_handle2)
+            {
+                self.contact_force_events.push(ContactForceEvent {
+                    pair: collision.pair(),
+                    total_impulse: collision.total_impulse(),
+                    total_normal_impulse: collision.normal_impulse,
+                    total_tangent_impulse: collision.tangent_impulse,
+                });
+            }
         }
     }
 
+    pub fn drain_contact_force_events(&mut self) -> impl Iterator<Item = ContactForceEvent> + '_ {
+        self.contact_force_events.drain(..)
+    }
+
+    // Events queue up until they are drained
+    pub fn drain_collision_events(&mut self) -> impl Iterator<Item = CollisionEvent> + '_ {
+        self.collision_events.drain(..)
+    }
+
+    pub fn drain_intersection_events(&mut self) -> impl Iterator<Item = IntersectionEvent> + '_ {
+        self.intersection_events.drain(..)
+    }
+
+    pub fn intersections(&self) -> &[CollisionPair] {
+        &self.intersections
+    }
+
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    pub fn collision(
+        &self,
+        handle1: ColliderHandle,
+        handle2: ColliderHandle,
+    ) -> Option<&Collision> {
+        self.collisions.iter().find(|collision| {
+            (collision.collider_handle1, collision.collider_handle2) == (handle1, handle2)
+                || (collision.collider_handle1, collision.collider_handle2) == (handle2, handle1)
+        })
+    }
+
     pub fn fixed_dt(&self) -> f32 {
         self.fixed_dt
     }
@@ -100,26 +349,627 @@ impl PhysicsPipeline {
         self.fixed_dt = fixed_dt;
     }
 
+    // The new broadphase picks up every collider on the next step
+    pub fn set_broad_phase(&mut self, broad_phase: Box<dyn BroadPhase>) {
+        self.broad_phase = broad_phase;
+    }
+
+    // Also queue a `CollisionEvent::Persisted` for every pair that keeps
+    // touching, on every step
+    pub fn set_persisted_collision_events(&mut self, enabled: bool) {
+        self.persisted_collision_events = enabled;
+    }
+
+    // Replaces the hooks that are called while looking for collisions. No
+    // hooks are set by default.
+    pub fn set_hooks(&mut self, hooks: Box<dyn PhysicsHooks>) {
+        self.hooks = hooks;
+    }
+
+    // Returns the applied normal impulse and the friction impulse along
+    // `normal.perp()`. The impulses act at the contact point, so they turn the
+    // bodies too.
     fn apply_impulse(
-        &self,
-        rb1: &mut RigidBody,
-        rb2: &mut RigidBody,
+        mut rb1: Option<&mut RigidBody>,
+        mut rb2: Option<&mut RigidBody>,
+        collision: &Collision,
+    ) -> (f32, f32) {
+        let manifold = &collision.manifold;
+        let point = (manifold.contact_point_a + manifold.contact_point_b) / 2.0;
+        let inv_mass_along = |rb1: Option<&RigidBody>, rb2: Option<&RigidBody>, direction| {
+            rb1.map_or(0.0, |rb| rb.inv_mass_at_point(point, direction))
+                + rb2.map_or(0.0, |rb| rb.inv_mass_at_point(point, direction))
+        };
+        let relative_velocity = |rb1: Option<&RigidBody>, rb2: Option<&RigidBody>| {
+            rb2.map_or(Vec2::ZERO, |rb| rb.velocity_at_point(point))
+                - rb1.map_or(Vec2::ZERO, |rb| rb.velocity_at_point(point))
+        };
+        let apply = |rb1: Option<&mut RigidBody>, rb2: Option<&mut RigidBody>, impulse: Vec2| {
+            if let Some(rb1) = rb1 {
+                rb1.apply_impulse_at_point(-impulse, point);
+            }
+            if let Some(rb2) = rb2 {
+                rb2.apply_impulse_at_point(impulse, point);
+            }
+        };
+
+        let normal = manifold.normal;
+        let inv_mass_sum = inv_mass_along(rb1.as_deref(), rb2.as_deref(), normal);
+        if inv_mass_sum == 0.0 {
+            return (0.0, 0.0);
+        }
+        let relative_vel = relative_velocity(rb1.as_deref(), rb2.as_deref());
+        let are_moving_apart = relative_vel.dot(normal) > 0.0;
+        if are_moving_apart {
+            return (0.0, 0.0);
+        }
+        let e = collision.restitution;
+        let impulse_vel = -((1.0 + e) * relative_vel.dot(normal));
+        let impulse = impulse_vel / inv_mass_sum;
+        apply(rb1.as_deref_mut(), rb2.as_deref_mut(), impulse * normal);
+
+        // Coulomb friction. The total friction impulse over all iterations is
+        // limited by the total normal impulse, so only the change is applied.
+        let tangent = normal.perp();
+        let tangent_vel = (relative_velocity(rb1.as_deref(), rb2.as_deref())
+            + collision.surface_velocity)
+            .dot(tangent);
+        let inv_mass_sum = inv_mass_along(rb1.as_deref(), rb2.as_deref(), tangent);
+        let max_friction_impulse = collision.friction * (collision.normal_impulse + impulse);
+        let friction_impulse = (collision.tangent_impulse - tangent_vel / inv_mass_sum)
+            .clamp(-max_friction_impulse, max_friction_impulse)
+            - collision.tangent_impulse;
+        apply(rb1, rb2, friction_impulse * tangent);
+
+        (impulse, friction_impulse)
+    }
+
+    fn correct_positions(
+        mut rb1: Option<&mut RigidBody>,
+        mut rb2: Option<&mut RigidBody>,
         manifold: &CollisionManifold,
+        colliders: &mut ColliderSet,
     ) {
-        let inv_mass_sum = rb1.inv_mass + rb2.inv_mass;
+        let inv_mass1 = rb1.as_ref().map_or(0.0, |rb| rb.inv_mass);
+        let inv_mass2 = rb2.as_ref().map_or(0.0, |rb| rb.inv_mass);
+        let inv_mass_sum = inv_mass1 + inv_mass2;
         if inv_mass_sum == 0.0 {
             return;
         }
-        let relative_vel = rb2.linear_velocity - rb1.linear_velocity;
-        let are_moving_apart = relative_vel.dot(manifold.normal) > 0.0;
-        if are_moving_apart {
-            return;
+
+        let depth = (manifold.depth - Self::PENETRATION_SLOP).max(0.0);
+        let correction = depth * Self::POSITION_CORRECTION_PERCENT / inv_mass_sum * manifold.normal;
+        // Resting bodies don't move in integrate_velocity, so their colliders
+        // have to follow right away
+        if let Some(rb1) = rb1.as_mut() {
+            rb1.set_position(rb1.position - rb1.inv_mass * correction, colliders);
         }
-        let e = rb1.cor.min(rb2.cor);
-        let impulse_vel = -((1.0 + e) * relative_vel.dot(manifold.normal));
-        let impulse = impulse_vel / inv_mass_sum;
+        if let Some(rb2) = rb2.as_mut() {
+            rb2.set_position(rb2.position + rb2.inv_mass * correction, colliders);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+    use crate::{
+        collision::{Collider, ColliderMaterial, ColliderShape},
+        Box2D, Circle,
+    };
+
+    const GRAVITY: Vec2 = Vec2::new(0.0, -10.0);
+
+    struct World {
+        pipeline: PhysicsPipeline,
+        bodies: RigidBodySet,
+        colliders: ColliderSet,
+    }
+
+    impl World {
+        fn new() -> Self {
+            Self {
+                pipeline: PhysicsPipeline::new(1.0 / 60.0),
+                bodies: RigidBodySet::new(),
+                colliders: ColliderSet::new(),
+            }
+        }
+
+        // Static box with its top at y = 0
+        fn add_ground(&mut self) -> ColliderHandle {
+            let shape = Box2D::new(Vec2::new(-10.0, -1.0), Vec2::new(10.0, 0.0), 0.0);
+            self.colliders
+                .insert(Collider::new(ColliderShape::Box2D(shape)))
+        }
+
+        // Thin static platform with its top at y = 0 that can be passed from
+        // below
+        fn add_one_way_platform(&mut self) -> ColliderHandle {
+            let shape = Box2D::new(Vec2::new(-2.0, -0.1), Vec2::new(2.0, 0.0), 0.0);
+            let mut collider = Collider::new(ColliderShape::Box2D(shape));
+            collider.one_way_direction = Some(Vec2::Y);
+            self.colliders.insert(collider)
+        }
+
+        fn add_ball(&mut self, position: Vec2) -> (RigidBodyHandle, ColliderHandle) {
+            let mut body = RigidBody::new(position, 0.0);
+            body.set_mass(1.0);
+            let body_handle = self.bodies.insert(body);
+            let mut collider = Collider::new(ColliderShape::Circle(Circle::new(Vec2::ZERO, 0.5)));
+            collider.material.restitution = 0.0;
+            let collider_handle =
+                self.colliders
+                    .insert_with_parent(collider, body_handle, &mut self.bodies);
+            (body_handle, collider_handle)
+        }
+
+        // Unit box with friction and without inertia, so it can't tip over
+        fn add_box(&mut self, position: Vec2, rotation: f32) -> (RigidBodyHandle, ColliderHandle) {
+            let mut body = RigidBody::new(position, rotation);
+            body.set_mass(1.0);
+            let body_handle = self.bodies.insert(body);
+            let shape = Box2D::new(Vec2::splat(-0.5), Vec2::splat(0.5), 0.0);
+            let mut collider = Collider::new(ColliderShape::Box2D(shape));
+            collider.material = ColliderMaterial::new(0.5, 0.0);
+            let collider_handle =
+                self.colliders
+                    .insert_with_parent(collider, body_handle, &mut self.bodies);
+            (body_handle, collider_handle)
+        }
+
+        fn run(&mut self, steps: usize) {
+            for _ in 0..steps {
+                self.step(GRAVITY);
+            }
+        }
+
+        fn step(&mut self, gravity: Vec2) {
+            self.pipeline.fixed_step(
+                gravity,
+                &mut self.bodies,
+                &mut self.colliders,
+                &ForceGeneratorSet::new(),
+                &ForceRegistry::new(),
+            );
+        }
+    }
+
+    #[test]
+    fn parentless_collider_stops_falling_body() {
+        let mut world = World::new();
+        world.add_ground();
+        let (ball, _) = world.add_ball(Vec2::new(0.0, 2.0));
+        world.run(180);
+
+        // Resting on top of the ground, not falling through it
+        let y = world.bodies[ball].position().y;
+        assert!((0.45..0.55).contains(&y), "ball ended up at y = {y}");
+    }
+
+    #[test]
+    fn removed_body_leaves_static_colliders() {
+        let mut world = World::new();
+        let (platform, platform_collider) = world.add_ball(Vec2::ZERO);
+        let (ball, _) = world.add_ball(Vec2::new(0.0, 2.0));
+        world
+            .bodies
+            .remove(platform, &mut world.colliders, &mut ForceRegistry::new());
+        assert_eq!(world.colliders[platform_collider].parent, None);
+
+        // The ball lands on the collider that stayed behind
+        world.run(180);
+        assert!(world.bodies[ball].position().y > 0.95);
+    }
+
+    #[test]
+    fn contacts_slow_down_spinning_bodies() {
+        let mut world = World::new();
+        world.add_ground();
+        let (ball, collider) = world.add_ball(Vec2::new(0.0, 0.5));
+        world.colliders[collider].material.friction = 0.5;
+        // A solid disc of mass 1 and radius 0.5
+        world.bodies[ball].set_angular_inertia(0.125);
+        world.bodies[ball].set_angular_velocity(10.0);
+        world.run(60);
+
+        // Friction turns the spin into rolling
+        let body = &world.bodies[ball];
+        assert!(
+            body.angular_velocity() < 5.0,
+            "still spins at {}",
+            body.angular_velocity()
+        );
+        assert!(body.linear_velocity().x < -1.0);
+    }
+
+    #[test]
+    fn kinematic_bodies_push_dynamic_ones() {
+        let mut world = World::new();
+        let (ball, _) = world.add_ball(Vec2::new(1.5, 0.0));
+        let shape = ColliderShape::Box2D(Box2D::new(Vec2::splat(-0.5), Vec2::splat(0.5), 0.0));
+        let pusher = world.bodies.insert(RigidBody::new(Vec2::ZERO, 0.0));
+        world
+            .colliders
+            .insert_with_parent(Collider::new(shape), pusher, &mut world.bodies);
+        world.bodies[pusher].set_linear_velocity(Vec2::new(3.0, 0.0));
+
+        for _ in 0..60 {
+            world.step(Vec2::ZERO);
+        }
+        let pusher_x = world.bodies[pusher].position().x;
+        assert!((pusher_x - 3.0).abs() < 1e-3);
+        assert!(world.bodies[ball].position().x > pusher_x + 0.9);
+    }
+
+    #[test]
+    fn collision_events_start_and_stop_once() {
+        let mut world = World::new();
+        let ground = world.add_ground();
+        let (ball, ball_collider) = world.add_ball(Vec2::new(0.0, 1.0));
+        world.run(120);
+        let events: Vec<_> = world.pipeline.drain_collision_events().collect();
+        assert_eq!(events.len(), 1);
+        assert!(events[0].started());
+        let pair = events[0].pair();
+        assert_eq!(
+            pair.key(),
+            (ground.min(ball_collider), ground.max(ball_collider))
+        );
+        assert_eq!(pair.body_handle1.or(pair.body_handle2), Some(ball));
+
+        world.bodies[ball].set_position(Vec2::new(0.0, 5.0), &mut world.colliders);
+        world.run(2);
+        let events: Vec<_> = world.pipeline.drain_collision_events().collect();
+        assert_eq!(events, [CollisionEvent::Stopped(pair)]);
+    }
+
+    #[test]
+    fn persisted_collision_events_are_opt_in() {
+        let mut world = World::new();
+        world.add_ground();
+        world.add_ball(Vec2::new(0.0, 0.5));
+        world.pipeline.set_persisted_collision_events(true);
+        world.run(3);
+        let events: Vec<_> = world.pipeline.drain_collision_events().collect();
+        assert_eq!(events.len(), 3);
+        assert!(events[0].started());
+        assert!(events[1..]
+            .iter()
+            .all(|event| matches!(event, CollisionEvent::Persisted(_))));
+    }
+
+    #[test]
+    fn sensors_report_intersections_without_contacts() {
+        let mut world = World::new();
+        let sensor = world.add_ground();
+        world.colliders[sensor].is_sensor = true;
+        let (ball, _) = world.add_ball(Vec2::new(0.0, 1.0));
+        world.run(60);
+
+        // The ball fell through as if the sensor wasn't there
+        assert!(world.bodies[ball].position().y < -2.0);
+        assert_eq!(world.pipeline.drain_collision_events().count(), 0);
+        let events: Vec<_> = world.pipeline.drain_intersection_events().collect();
+        assert_eq!(events.len(), 2);
+        assert!(events[0].started());
+        assert!(events[1].stopped());
+        assert_eq!(events[0].pair(), events[1].pair());
+    }
+
+    #[test]
+    fn contact_force_events_count_friction() {
+        let mut world = World::new();
+        let ground = world.add_ground();
+        world.colliders[ground].contact_force_event_threshold = Some(0.18);
+        world.colliders[ground].material.friction = 0.5;
+        let (ball, collider) = world.add_ball(Vec2::new(0.0, 0.5));
+        world.colliders[collider].material.friction = 0.5;
+        world.run(30);
+
+        // Resting takes an impulse of about 1/6 per step, below the threshold
+        let collision = world.pipeline.collision(ground, collider).unwrap();
+        assert!((collision.normal_impulse - 1.0 / 6.0).abs() < 0.01);
+        assert_eq!(world.pipeline.drain_contact_force_events().count(), 0);
+
+        // Sliding adds friction, which pushes the total over it
+        world.bodies[ball].set_linear_velocity(Vec2::new(5.0, 0.0));
+        world.step(GRAVITY);
+        let events: Vec<_> = world.pipeline.drain_contact_force_events().collect();
+        assert_eq!(events.len(), 1);
+        assert!(events[0].total_normal_impulse < 0.18);
+        assert!(events[0].total_impulse > 0.18);
+    }
+
+    #[test]
+    fn boxes_rest_on_slopes_below_the_friction_angle() {
+        let mut world = World::new();
+        let angle = 20_f32.to_radians();
+        let slope = Box2D::new(Vec2::new(-10.0, -1.0), Vec2::new(10.0, 0.0), angle);
+        let mut slope = Collider::new(ColliderShape::Box2D(slope));
+        slope.material = ColliderMaterial::new(0.5, 0.0);
+        world.colliders.insert(slope);
+
+        // A stack of two boxes
+        let up = Vec2::from_angle(angle).rotate(Vec2::Y);
+        let stack: Vec<_> = [0.995, 1.99]
+            .into_iter()
+            .map(|height| {
+                let position = Vec2::new(0.0, -0.5) + up * height;
+                (world.add_box(position, angle).0, position)
+            })
+            .collect();
+
+        world.run(120);
+        for (body, position) in stack {
+            let moved = world.bodies[body].position().distance(position);
+            assert!(moved < 0.01, "a box slid {moved} down the slope");
+        }
+    }
+
+    #[test]
+    fn friction_is_limited_by_the_total_normal_impulse() {
+        let mut world = World::new();
+        let ground = world.add_ground();
+        world.colliders[ground].material.friction = 0.5;
+        let (body, collider) = world.add_box(Vec2::new(0.0, 0.5), 0.0);
+        world.run(10);
+
+        // A ball hits the box from the side after the ground already held the
+        // box up, so the box slides and friction is as large as it gets
+        let (ball, _) = world.add_ball(Vec2::new(-0.99, 0.5));
+        world.bodies[ball].set_linear_velocity(Vec2::new(4.0, 0.0));
+        world.step(GRAVITY);
+        let collision = world.pipeline.collision(ground, collider).unwrap();
+        assert!(world.bodies[body].linear_velocity().x > 0.0);
+        assert!((collision.tangent_impulse.abs() - 0.5 * collision.normal_impulse).abs() < 1e-4);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn default_material_keeps_old_contacts() {
+        let mut world = World::new();
+        let ground = world.add_ground();
+        let (ball, collider) = world.add_ball(Vec2::new(0.0, 0.5));
+        world.colliders[collider].material = ColliderMaterial::default();
+        world.bodies[ball].set_linear_velocity(Vec2::new(5.0, 0.0));
+        world.step(GRAVITY);
+        let collision = world.pipeline.collision(ground, collider).unwrap();
+        assert_eq!(collision.friction, 0.0);
+        assert_eq!(collision.restitution, 1.0);
+        // No friction slows the ball down
+        world.run(30);
+        assert_eq!(world.bodies[ball].linear_velocity().x, 5.0);
+
+        // The restitution of the body still applies
+        world.bodies[ball].cor = 0.25;
+        world.bodies[ball].set_position(Vec2::new(0.0, 0.5), &mut world.colliders);
+        world.step(GRAVITY);
+        let collision = world.pipeline.collision(ground, collider).unwrap();
+        assert_eq!(collision.restitution, 0.25);
+    }
+
+    #[test]
+    fn bodies_jump_up_through_one_way_platforms() {
+        let mut world = World::new();
+        world.add_one_way_platform();
+        let (ball, _) = world.add_ball(Vec2::new(0.0, -1.5));
+        world.bodies[ball].set_linear_velocity(Vec2::new(0.0, 8.0));
+        while world.bodies[ball].linear_velocity().y > 0.0 {
+            world.step(GRAVITY);
+        }
+        assert!(world.bodies[ball].position().y > 1.0);
+
+        // And land on top on the way back down
+        for _ in 0..180 {
+            world.step(GRAVITY);
+        }
+        let y = world.bodies[ball].position().y;
+        assert!((0.45..0.55).contains(&y), "ball ended up at y = {y}");
+    }
+
+    #[test]
+    fn bodies_land_on_one_way_platforms() {
+        let mut world = World::new();
+        world.add_one_way_platform();
+        let (ball, _) = world.add_ball(Vec2::new(0.0, 2.0));
+        for _ in 0..180 {
+            world.step(GRAVITY);
+        }
+        let y = world.bodies[ball].position().y;
+        assert!((0.45..0.55).contains(&y), "ball ended up at y = {y}");
+    }
+
+    #[test]
+    fn one_way_platforms_ignore_overlaps_that_started_below() {
+        let mut world = World::new();
+        world.add_one_way_platform();
+        let (ball, _) = world.add_ball(Vec2::new(0.0, -0.705));
+        world.bodies[ball].set_linear_velocity(Vec2::new(0.0, 3.0));
+        while world.bodies[ball].position().y < 0.49 {
+            world.step(Vec2::ZERO);
+        }
+        // Barely inside the top of the platform, which would be accepted as
+        // a landing if the overlap had just started
+        assert!(world.bodies[ball].position().y < 0.5);
+
+        world.bodies[ball].set_linear_velocity(Vec2::new(0.0, -3.0));
+        for _ in 0..40 {
+            world.step(Vec2::ZERO);
+        }
+        let y = world.bodies[ball].position().y;
+        assert!(y < -0.6, "ball ended up at y = {y}");
+    }
+
+    #[test]
+    fn one_way_platforms_turn_with_their_body() {
+        let mut world = World::new();
+        let shape = Box2D::new(Vec2::new(-2.0, -0.1), Vec2::new(2.0, 0.1), 0.0);
+        let mut platform = Collider::new(ColliderShape::Box2D(shape));
+        platform.one_way_direction = Some(Vec2::Y);
+        let body = world.bodies.insert(RigidBody::new(Vec2::ZERO, 0.0));
+        world
+            .colliders
+            .insert_with_parent(platform, body, &mut world.bodies);
+        // Upside down, the platform only stops bodies from below
+        world.bodies[body].set_rotation(PI, &mut world.colliders);
+        let (ball, _) = world.add_ball(Vec2::new(0.0, 2.0));
+        world.run(120);
+        assert!(world.bodies[ball].position().y < -1.0);
+    }
+
+    #[test]
+    fn hooks_can_filter_out_pairs() {
+        struct IgnoreCollider(ColliderHandle);
+        impl PhysicsHooks for IgnoreCollider {
+            fn filter_contact_pair(&self, context: &PairFilterContext) -> bool {
+                let pair = context.pair;
+                pair.collider_handle1 != self.0 && pair.collider_handle2 != self.0
+            }
+        }
+
+        let mut world = World::new();
+        let ground = world.add_ground();
+        let (ball, _) = world.add_ball(Vec2::new(0.0, 2.0));
+        world.pipeline.set_hooks(Box::new(IgnoreCollider(ground)));
+        for _ in 0..120 {
+            world.step(GRAVITY);
+        }
+        assert!(world.bodies[ball].position().y < -1.0);
+        assert_eq!(world.pipeline.drain_collision_events().count(), 0);
+    }
+
+    #[test]
+    fn hooks_can_change_friction() {
+        struct Sticky;
+        impl PhysicsHooks for Sticky {
+            fn modify_contact(&self, context: &mut ContactModificationContext) -> bool {
+                context.collision.friction = 1.0;
+                true
+            }
+        }
+
+        let slide = |hooks: Box<dyn PhysicsHooks>| {
+            let mut world = World::new();
+            world.add_ground();
+            let (ball, _) = world.add_ball(Vec2::new(0.0, 0.5));
+            world.pipeline.set_hooks(hooks);
+            world.bodies[ball].set_linear_velocity(Vec2::new(5.0, 0.0));
+            for _ in 0..30 {
+                world.step(GRAVITY);
+            }
+            world.bodies[ball].linear_velocity().x
+        };
+        // Frictionless by default, so only the hook slows the ball down
+        assert!((slide(Box::new(())) - 5.0).abs() < 1e-4);
+        assert!(slide(Box::new(Sticky)) < 4.0);
+    }
+
+    #[test]
+    fn resting_bodies_dont_sink() {
+        let mut world = World::new();
+        let ground = world.add_ground();
+        let (ball, collider) = world.add_ball(Vec2::new(0.0, 0.5));
+        for _ in 0..600 {
+            world.step(GRAVITY);
+        }
+        // Without position correction gravity would sink the ball by about
+        // g * dt^2 every step
+        let y = world.bodies[ball].position().y;
+        assert!(y > 0.48, "ball sank to y = {y}");
+        let depth = world
+            .pipeline
+            .collision(ground, collider)
+            .unwrap()
+            .manifold
+            .depth;
+        assert!(depth < 0.02, "ball is {depth} deep in the ground");
+    }
+
+    #[test]
+    fn stacked_bodies_dont_sink() {
+        let mut world = World::new();
+        world.add_ground();
+        let stack: Vec<_> = (0..3)
+            .map(|i| world.add_box(Vec2::new(0.0, 0.55 + 1.05 * i as f32), 0.0).0)
+            .collect();
+        let heights = |world: &World| -> Vec<f32> {
+            stack
+                .iter()
+                .map(|body| world.bodies[*body].position().y)
+                .collect()
+        };
+        world.run(300);
+        let settled = heights(&world);
+        world.run(300);
+
+        // Each landing leaves the boxes a little inside each other, which is
+        // pushed back out until only the slop is left
+        for (y, settled_y) in heights(&world).into_iter().zip(settled) {
+            assert!(
+                (y - settled_y).abs() < 1e-3,
+                "box sank from {settled_y} to {y}"
+            );
+        }
+        assert_eq!(world.pipeline.collisions().len(), 3);
+        for collision in world.pipeline.collisions() {
+            let depth = collision.manifold.depth;
+            assert!(depth < 0.02, "boxes are {depth} inside each other");
+        }
+    }
+
+    #[test]
+    fn overlapping_bodies_are_pushed_apart_without_bouncing() {
+        let mut world = World::new();
+        world.add_ground();
+        let (ball, _) = world.add_ball(Vec2::new(0.0, 0.3));
+        for _ in 0..30 {
+            world.step(Vec2::ZERO);
+        }
+        let body = &world.bodies[ball];
+        let y = body.position().y;
+        assert!(y > 0.48 && y < 0.5, "ball ended up at y = {y}");
+        assert_eq!(body.linear_velocity(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn conveyor_belts_carry_bodies_along() {
+        let mut world = World::new();
+        let belt = world.add_ground();
+        world.colliders[belt].material.friction = 0.5;
+        world.colliders[belt].material.tangent_speed = 2.0;
+        let (ball, collider) = world.add_ball(Vec2::new(0.0, 0.5));
+        world.colliders[collider].material.friction = 0.5;
+        world.run(60);
+        assert!((world.bodies[ball].linear_velocity().x - 2.0).abs() < 0.01);
+
+        // Reversing the belt brings the ball back
+        world.colliders[belt].material.tangent_speed = -2.0;
+        world.run(120);
+        assert!((world.bodies[ball].linear_velocity().x + 2.0).abs() < 0.01);
+        let y = world.bodies[ball].position().y;
+        assert!((0.45..0.55).contains(&y), "ball ended up at y = {y}");
+    }
 
-        rb1.linear_velocity -= rb1.inv_mass * impulse * manifold.normal;
-        rb2.linear_velocity += rb2.inv_mass * impulse * manifold.normal;
+    #[test]
+    fn conveyor_belts_turn_with_their_body() {
+        let mut world = World::new();
+        let shape = Box2D::new(Vec2::new(-10.0, -0.5), Vec2::new(10.0, 0.5), 0.0);
+        let mut belt = Collider::new(ColliderShape::Box2D(shape));
+        belt.material = ColliderMaterial::new(0.5, 0.0);
+        belt.material.tangent_speed = 2.0;
+        let body = world
+            .bodies
+            .insert(RigidBody::new(Vec2::new(0.0, -0.5), 0.0));
+        world
+            .colliders
+            .insert_with_parent(belt, body, &mut world.bodies);
+        // Upside down, the top of the belt runs the other way
+        world.bodies[body].set_rotation(PI, &mut world.colliders);
+        let (ball, collider) = world.add_ball(Vec2::new(0.0, 0.5));
+        world.colliders[collider].material.friction = 0.5;
+        world.run(60);
+        assert!((world.bodies[ball].linear_velocity().x + 2.0).abs() < 0.01);
     }
 }
@@ -0,0 +1,5 @@
+mod query_filter;
+mod query_pipeline;
+
+pub use query_filter::*;
+pub use query_pipeline::*;
@@ -0,0 +1,62 @@
+use crate::{
+    collision::{Collider, ColliderHandle, InteractionGroups},
+    RigidBodyHandle,
+};
+
+pub type QueryPredicate<'a> = &'a dyn Fn(ColliderHandle, &Collider) -> bool;
+
+// Decides which colliders a scene query looks at
+#[derive(Clone, Copy, Default)]
+pub struct QueryFilter<'a> {
+    // Only colliders whose collision groups pass `groups.test` are considered
+    pub groups: InteractionGroups,
+    pub exclude_sensors: bool,
+    pub exclude_colliders: &'a [ColliderHandle],
+    // Skips every collider attached to this body, e.g. the one casting the ray
+    pub exclude_rigid_body: Option<RigidBodyHandle>,
+    pub predicate: Option<QueryPredicate<'a>>,
+}
+
+impl<'a> QueryFilter<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_groups(mut self, groups: InteractionGroups) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    pub fn excluding_sensors(mut self) -> Self {
+        self.exclude_sensors = true;
+        self
+    }
+
+    pub fn excluding_colliders(mut self, colliders: &'a [ColliderHandle]) -> Self {
+        self.exclude_colliders = colliders;
+        self
+    }
+
+    pub fn excluding_rigid_body(mut self, body: RigidBodyHandle) -> Self {
+        self.exclude_rigid_body = Some(body);
+        self
+    }
+
+    pub fn with_predicate(
+        mut self,
+        predicate: &'a dyn Fn(ColliderHandle, &Collider) -> bool,
+    ) -> Self {
+        self.predicate = Some(predicate);
+        self
+    }
+
+    pub fn test(&self, handle: ColliderHandle, collider: &Collider) -> bool {
+        self.groups.test(collider.collision_groups)
+            && !(self.exclude_sensors && collider.is_sensor)
+            && !self.exclude_colliders.contains(&handle)
+            && (self.exclude_rigid_body.is_none() || collider.parent != self.exclude_rigid_body)
+            && self
+                .predicate
+                .is_none_or(|predicate| predicate(handle, collider))
+    }
+}
@@ -0,0 +1,455 @@
+use glam::Vec2;
+
+use crate::{
+    collision::{
+        BroadPhase, ColliderHandle, ColliderSet, ColliderShape, DynamicTreeBroadPhase,
+        ShapeCastResult2D,
+    },
+    Aabb, Ray2D, RaycastResult2D,
+};
+
+use super::QueryFilter;
+
+// Answers questions about the whole `ColliderSet`, using a dynamic AABB tree
+// to skip colliders that are nowhere near the query. `update` has to be
+// called after the colliders move, e.g. once after every physics step.
+#[derive(Debug, Clone, Default)]
+pub struct QueryPipeline {
+    broad_phase: DynamicTreeBroadPhase,
+}
+
+impl QueryPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, colliders: &ColliderSet) {
+        self.broad_phase.update(colliders);
+    }
+
+    // Closest collider hit by the ray within `max_t`. See
+    // `ColliderShape::cast_ray` for the meaning of `solid`.
+    pub fn cast_ray(
+        &self,
+        colliders: &ColliderSet,
+        ray: Ray2D,
+        max_t: f32,
+        solid: bool,
+        filter: QueryFilter,
+    ) -> Option<(ColliderHandle, RaycastResult2D)> {
+        let mut closest: Option<(ColliderHandle, RaycastResult2D)> = None;
+        self.broad_phase.tree().cast_ray(ray, max_t, |_, handle| {
+            let Some(hit) = Self::cast_ray_against(colliders, handle, ray, max_t, solid, filter)
+            else {
+                return f32::MAX;
+            };
+
+            // Leaves aren't visited in order, ties go to the smaller handle
+            // to keep the result deterministic
+            let is_closer = closest
+                .as_ref()
+                .is_none_or(|(closest_handle, closest_hit)| {
+                    hit.t < closest_hit.t || (hit.t == closest_hit.t && handle < *closest_handle)
+                });
+            if !is_closer {
+                return f32::MAX;
+            }
+
+            let t = hit.t;
+            closest = Some((handle, hit));
+            t
+        });
+        closest
+    }
+
+    // Every collider hit by the ray within `max_t`, closest first
+    pub fn cast_ray_all(
+        &self,
+        colliders: &ColliderSet,
+        ray: Ray2D,
+        max_t: f32,
+        solid: bool,
+        filter: QueryFilter,
+    ) -> Vec<(ColliderHandle, RaycastResult2D)> {
+        let mut hits = Vec::new();
+        self.broad_phase.tree().cast_ray(ray, max_t, |_, handle| {
+            if let Some(hit) = Self::cast_ray_against(colliders, handle, ray, max_t, solid, filter)
+            {
+                hits.push((handle, hit));
+            }
+            f32::MAX
+        });
+        hits.sort_by(|(h1, hit1), (h2, hit2)| hit1.t.total_cmp(&hit2.t).then(h1.cmp(h2)));
+        hits
+    }
+
+    // First collider hit by `shape` as it moves by `velocity` per unit of time,
+    // up to `max_toi`. Colliders that already overlap the shape are hit at a
+    // time of impact of zero.
+    pub fn cast_shape(
+        &self,
+        colliders: &ColliderSet,
+        shape: &ColliderShape,
+        velocity: Vec2,
+        max_toi: f32,
+        filter: QueryFilter,
+    ) -> Option<(ColliderHandle, ShapeCastResult2D)> {
+        let swept_aabb = shape.compute_swept_aabb(velocity * max_toi);
+        let mut closest: Option<(ColliderHandle, ShapeCastResult2D)> = None;
+        self.broad_phase.tree().query_aabb(swept_aabb, |_, handle| {
+            let Some(collider) = colliders.get(handle) else {
+                return true;
+            };
+            if !filter.test(handle, collider) {
+                return true;
+            }
+
+            let max_toi = closest.as_ref().map_or(max_toi, |(_, hit)| hit.toi);
+            let Some(hit) = shape.cast_shape(velocity, &collider.shape, max_toi) else {
+                return true;
+            };
+            let is_closer = closest
+                .as_ref()
+                .is_none_or(|(closest_handle, closest_hit)| {
+                    hit.toi < closest_hit.toi
+                        || (hit.toi == closest_hit.toi && handle < *closest_handle)
+                });
+            if is_closer {
+                closest = Some((handle, hit));
+            }
+            true
+        });
+        closest
+    }
+
+    // Calls `callback` with every collider containing `point`, until it
+    // returns false
+    pub fn intersections_with_point(
+        &self,
+        colliders: &ColliderSet,
+        point: Vec2,
+        filter: QueryFilter,
+        mut callback: impl FnMut(ColliderHandle) -> bool,
+    ) {
+        self.broad_phase
+            .tree()
+            .query_point(point, |_, handle| match colliders.get(handle) {
+                Some(collider)
+                    if filter.test(handle, collider) && collider.shape.contains_point(point) =>
+                {
+                    callback(handle)
+                }
+                _ => true,
+            });
+    }
+
+    pub fn colliders_at_point(
+        &self,
+        colliders: &ColliderSet,
+        point: Vec2,
+        filter: QueryFilter,
+    ) -> impl Iterator<Item = ColliderHandle> {
+        Self::collect_sorted(|callback| {
+            self.intersections_with_point(colliders, point, filter, callback)
+        })
+    }
+
+    // Calls `callback` with every collider whose bounds overlap `aabb`, until
+    // it returns false
+    pub fn intersections_with_aabb(
+        &self,
+        colliders: &ColliderSet,
+        aabb: Aabb,
+        filter: QueryFilter,
+        mut callback: impl FnMut(ColliderHandle) -> bool,
+    ) {
+        self.broad_phase
+            .tree()
+            .query_aabb(aabb, |_, handle| match colliders.get(handle) {
+                Some(collider)
+                    if filter.test(handle, collider)
+                        && collider.shape.compute_aabb().intersects(aabb) =>
+                {
+                    callback(handle)
+                }
+                _ => true,
+            });
+    }
+
+    pub fn colliders_in_aabb(
+        &self,
+        colliders: &ColliderSet,
+        aabb: Aabb,
+        filter: QueryFilter,
+    ) -> impl Iterator<Item = ColliderHandle> {
+        Self::collect_sorted(|callback| {
+            self.intersections_with_aabb(colliders, aabb, filter, callback)
+        })
+    }
+
+    // Calls `callback` with every collider touching `shape` once it is moved
+    // to `position` and `rotation`, until it returns false
+    pub fn intersections_with_shape(
+        &self,
+        colliders: &ColliderSet,
+        shape: &ColliderShape,
+        position: Vec2,
+        rotation: f32,
+        filter: QueryFilter,
+        mut callback: impl FnMut(ColliderHandle) -> bool,
+    ) {
+        let mut shape = shape.clone();
+        shape.update_position(position);
+        shape.update_rotation(rotation);
+        self.broad_phase
+            .tree()
+            .query_aabb(shape.compute_aabb(), |_, handle| {
+                match colliders.get(handle) {
+                    Some(collider)
+                        if filter.test(handle, collider)
+                            && shape.test_collision(&collider.shape).is_some() =>
+                    {
+                        callback(handle)
+                    }
+                    _ => true,
+                }
+            });
+    }
+
+    pub fn colliders_overlapping_shape(
+        &self,
+        colliders: &ColliderSet,
+        shape: &ColliderShape,
+        position: Vec2,
+        rotation: f32,
+        filter: QueryFilter,
+    ) -> impl Iterator<Item = ColliderHandle> {
+        Self::collect_sorted(|callback| {
+            self.intersections_with_shape(colliders, shape, position, rotation, filter, callback)
+        })
+    }
+
+    // Turns a callback query into a list, ordered by handle so that it
+    // doesn't depend on the layout of the tree
+    fn collect_sorted(
+        query: impl FnOnce(&mut dyn FnMut(ColliderHandle) -> bool),
+    ) -> std::vec::IntoIter<ColliderHandle> {
+        let mut handles = Vec::new();
+        query(&mut |handle| {
+            handles.push(handle);
+            true
+        });
+        handles.sort();
+        handles.into_iter()
+    }
+
+    fn cast_ray_against(
+        colliders: &ColliderSet,
+        handle: ColliderHandle,
+        ray: Ray2D,
+        max_t: f32,
+        solid: bool,
+        filter: QueryFilter,
+    ) -> Option<RaycastResult2D> {
+        // The tree may be older than the collider set
+        let collider = colliders.get(handle)?;
+        if !filter.test(handle, collider) {
+            return None;
+        }
+        collider.shape.cast_ray(ray, max_t, solid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collision::{test_utils::circle_collider, Collider, ColliderShape, InteractionGroups},
+        Box2D, Circle,
+    };
+
+    #[test]
+    fn ties_go_to_smaller_handle() {
+        let mut colliders = ColliderSet::new();
+        // Rays starting inside solid colliders hit them all at t = 0
+        let handles: Vec<_> = (0..8)
+            .map(|i| colliders.insert(circle_collider(i as f32 * 0.1, 0.0)))
+            .collect();
+        let mut query_pipeline = QueryPipeline::new();
+        query_pipeline.update(&colliders);
+
+        let ray = Ray2D::new(Vec2::new(0.5, 0.0), Vec2::X);
+        let (handle, hit) = query_pipeline
+            .cast_ray(&colliders, ray, f32::MAX, true, QueryFilter::new())
+            .unwrap();
+        assert_eq!(hit.t, 0.0);
+        assert_eq!(handle, *handles.iter().min().unwrap());
+    }
+
+    #[test]
+    fn ray_hits_closest_collider() {
+        let mut colliders = ColliderSet::new();
+        let near = colliders.insert(circle_collider(5.0, 0.0));
+        let far = colliders.insert(circle_collider(10.0, 0.0));
+        colliders.insert(circle_collider(5.0, 5.0));
+        let mut query_pipeline = QueryPipeline::new();
+        query_pipeline.update(&colliders);
+
+        let ray = Ray2D::new(Vec2::ZERO, Vec2::X);
+        let filter = QueryFilter::new();
+        let (handle, hit) = query_pipeline
+            .cast_ray(&colliders, ray, f32::MAX, true, filter)
+            .unwrap();
+        assert_eq!(handle, near);
+        assert!((hit.t - 4.0).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-6));
+
+        let hits = query_pipeline.cast_ray_all(&colliders, ray, f32::MAX, true, filter);
+        let handles: Vec<_> = hits.iter().map(|(handle, _)| *handle).collect();
+        assert_eq!(handles, vec![near, far]);
+
+        assert!(query_pipeline
+            .cast_ray(&colliders, ray, 3.0, true, filter)
+            .is_none());
+    }
+
+    #[test]
+    fn ray_respects_filter_and_solid_flag() {
+        let mut colliders = ColliderSet::new();
+        let mut excluded = circle_collider(5.0, 0.0);
+        excluded.collision_groups = InteractionGroups::new(0b10, u32::MAX);
+        let excluded = colliders.insert(excluded);
+        let sensor = colliders.insert(Collider {
+            is_sensor: true,
+            ..circle_collider(10.0, 0.0)
+        });
+        let wall = colliders.insert(Collider::new(ColliderShape::Box2D(Box2D::new(
+            Vec2::new(14.0, -5.0),
+            Vec2::new(20.0, 5.0),
+            0.0,
+        ))));
+        let mut query_pipeline = QueryPipeline::new();
+        query_pipeline.update(&colliders);
+
+        let ray = Ray2D::new(Vec2::ZERO, Vec2::X);
+        let cast = |filter| {
+            query_pipeline
+                .cast_ray(&colliders, ray, f32::MAX, true, filter)
+                .map(|(handle, _)| handle)
+        };
+        assert_eq!(cast(QueryFilter::new()), Some(excluded));
+        let groups = InteractionGroups::new(u32::MAX, 0b01);
+        assert_eq!(cast(QueryFilter::new().with_groups(groups)), Some(sensor));
+        let skip_excluded = [excluded];
+        let filter = QueryFilter::new()
+            .excluding_colliders(&skip_excluded)
+            .excluding_sensors();
+        assert_eq!(cast(filter), Some(wall));
+        let only_wall = |handle, _: &Collider| handle == wall;
+        assert_eq!(
+            cast(QueryFilter::new().with_predicate(&only_wall)),
+            Some(wall)
+        );
+
+        // Starting inside the wall
+        let ray = Ray2D::new(Vec2::new(16.0, 0.0), Vec2::X);
+        let filter = QueryFilter::new();
+        let (_, hit) = query_pipeline
+            .cast_ray(&colliders, ray, f32::MAX, true, filter)
+            .unwrap();
+        assert_eq!(hit.t, 0.0);
+        let (_, hit) = query_pipeline
+            .cast_ray(&colliders, ray, f32::MAX, false, filter)
+            .unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::X, 1e-6));
+    }
+
+    #[test]
+    fn shape_cast_finds_first_collider_in_the_way() {
+        let mut colliders = ColliderSet::new();
+        let wall = colliders.insert(Collider::new(ColliderShape::Box2D(Box2D::new(
+            Vec2::new(10.0, -5.0),
+            Vec2::new(12.0, 5.0),
+            0.0,
+        ))));
+        colliders.insert(circle_collider(20.0, 0.0));
+        // Out of the way of the swept shape
+        colliders.insert(circle_collider(5.0, 3.0));
+        let mut query_pipeline = QueryPipeline::new();
+        query_pipeline.update(&colliders);
+
+        let shape = ColliderShape::Box2D(Box2D::new(Vec2::splat(-1.0), Vec2::splat(1.0), 0.0));
+        let filter = QueryFilter::new();
+        let (handle, hit) = query_pipeline
+            .cast_shape(&colliders, &shape, Vec2::new(2.0, 0.0), 10.0, filter)
+            .unwrap();
+        assert_eq!(handle, wall);
+        assert!((hit.toi - 4.5).abs() < 1e-5);
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-6));
+        assert!(hit.point.abs_diff_eq(Vec2::new(10.0, 0.0), 1e-5));
+
+        assert!(query_pipeline
+            .cast_shape(&colliders, &shape, Vec2::new(2.0, 0.0), 4.0, filter)
+            .is_none());
+    }
+
+    #[test]
+    fn point_region_and_shape_queries() {
+        let mut colliders = ColliderSet::new();
+        let a = colliders.insert(circle_collider(0.0, 0.0));
+        let b = colliders.insert(circle_collider(1.5, 0.0));
+        // Rotated so that its corner reaches (2.0, 5.0 - sqrt(2))
+        let c = colliders.insert(Collider::new(ColliderShape::Box2D(Box2D::new(
+            Vec2::new(1.0, 4.0),
+            Vec2::new(3.0, 6.0),
+            std::f32::consts::PI / 4.0,
+        ))));
+        let mut query_pipeline = QueryPipeline::new();
+        query_pipeline.update(&colliders);
+        let filter = QueryFilter::new();
+
+        let at_point: Vec<_> = query_pipeline
+            .colliders_at_point(&colliders, Vec2::new(0.75, 0.0), filter)
+            .collect();
+        assert_eq!(at_point, vec![a.min(b), a.max(b)]);
+        // Inside the bounds of `c` but outside the rotated box itself
+        assert_eq!(
+            query_pipeline
+                .colliders_at_point(&colliders, Vec2::new(1.1, 4.1), filter)
+                .count(),
+            0
+        );
+
+        let region = Aabb::new(Vec2::new(2.0, -5.0), Vec2::new(10.0, 10.0));
+        let mut in_region: Vec<_> = query_pipeline
+            .colliders_in_aabb(&colliders, region, filter)
+            .collect();
+        in_region.sort();
+        let mut expected = vec![b, c];
+        expected.sort();
+        assert_eq!(in_region, expected);
+
+        // The callback form stops when asked to
+        let mut visited = 0;
+        query_pipeline.intersections_with_aabb(&colliders, region, filter, |_| {
+            visited += 1;
+            false
+        });
+        assert_eq!(visited, 1);
+
+        let probe = ColliderShape::Circle(Circle::new(Vec2::ZERO, 0.5));
+        let overlapping: Vec<_> = query_pipeline
+            .colliders_overlapping_shape(&colliders, &probe, Vec2::new(2.0, 3.2), 0.0, filter)
+            .collect();
+        assert_eq!(overlapping, vec![c]);
+        let excluded = [c];
+        let filter = QueryFilter::new().excluding_colliders(&excluded);
+        assert_eq!(
+            query_pipeline
+                .colliders_overlapping_shape(&colliders, &probe, Vec2::new(2.0, 3.2), 0.0, filter)
+                .count(),
+            0
+        );
+    }
+}